@@ -1,3 +1,5 @@
 pub mod quantum_bus;
+pub mod transport;
 
 pub use quantum_bus::{DeviceMesh, Device, DeviceCapability, ResourceRequest};
+pub use transport::{BusError, DeviceId, MemoryTransport};
@@ -51,8 +51,7 @@ impl Device {
         let mut score = 0;
         score += self.compute_power;
         score += (self.available_memory / (1024 * 1024)) as u32; // MB
-        score -= self.latency_ms * 10; // Penalize high latency
-        score
+        score.saturating_sub(self.latency_ms * 10) // Penalize high latency
     }
 }
 
@@ -104,10 +103,28 @@ impl DeviceMesh {
 
     /// Find best device for a task
     pub fn find_best_device(&self, required_memory: usize, required_compute: u32) -> Option<Device> {
+        self.best_device(required_memory, required_compute, None)
+    }
+
+    /// Find the best device other than this one, e.g. to hold remote memory
+    pub fn find_best_peer(&self, required_memory: usize, required_compute: u32) -> Option<Device> {
+        let local = self.local_device_id.load(Ordering::Relaxed);
+        self.best_device(required_memory, required_compute, Some(local))
+    }
+
+    fn best_device(
+        &self,
+        required_memory: usize,
+        required_compute: u32,
+        exclude: Option<u32>,
+    ) -> Option<Device> {
         let mut best: Option<Device> = None;
         let mut best_score = 0;
         
         for device in self.devices.iter().flatten() {
+            if Some(device.id) == exclude {
+                continue;
+            }
             if device.available_memory >= required_memory && 
                device.compute_power >= required_compute {
                 let score = device.capability_score();
@@ -0,0 +1,164 @@
+//! Remote memory transport - carries SMME Layer 3 requests over the Quantum Bus
+
+use crate::memory::spin::SpinLock;
+
+/// Mesh-wide device identifier, as in `Device::id`
+pub type DeviceId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusError {
+    /// No route to the device
+    Unreachable,
+    /// The peer refused the request (out of memory, bad address)
+    Rejected,
+}
+
+/// Memory operations a peer serves for SMME
+pub trait MemoryTransport: Sync {
+    /// Allocate `size` bytes on `device`, returning the address in its address space
+    fn remote_allocate(&self, device: DeviceId, size: usize) -> Result<usize, BusError>;
+    fn remote_read(&self, device: DeviceId, addr: usize, buf: &mut [u8]) -> Result<(), BusError>;
+    fn remote_write(&self, device: DeviceId, addr: usize, data: &[u8]) -> Result<(), BusError>;
+    fn remote_free(&self, device: DeviceId, addr: usize, size: usize) -> Result<(), BusError>;
+}
+
+/// Memory a loopback peer exposes
+pub const LOOPBACK_MEMORY: usize = 64 * 1024;
+/// Allocation unit of the loopback peer
+const LOOPBACK_CHUNK: usize = 256;
+const LOOPBACK_CHUNKS: usize = LOOPBACK_MEMORY / LOOPBACK_CHUNK;
+/// Where the loopback peer's memory starts in its own address space
+pub const LOOPBACK_BASE: usize = 0x8000_0000;
+
+struct LoopbackState {
+    memory: [u8; LOOPBACK_MEMORY],
+    // One entry per chunk: length in chunks of the block starting there, zero otherwise
+    blocks: [u16; LOOPBACK_CHUNKS],
+    used: [bool; LOOPBACK_CHUNKS],
+}
+
+/// A peer simulated in local memory, for tests and single-board bring-up
+pub struct LoopbackTransport {
+    device: DeviceId,
+    state: SpinLock<LoopbackState>,
+}
+
+impl LoopbackTransport {
+    pub const fn new(device: DeviceId) -> Self {
+        Self {
+            device,
+            state: SpinLock::new(LoopbackState {
+                memory: [0; LOOPBACK_MEMORY],
+                blocks: [0; LOOPBACK_CHUNKS],
+                used: [false; LOOPBACK_CHUNKS],
+            }),
+        }
+    }
+
+    fn route(&self, device: DeviceId) -> Result<(), BusError> {
+        if device == self.device {
+            Ok(())
+        } else {
+            Err(BusError::Unreachable)
+        }
+    }
+
+    /// Offset range of `addr..addr + len` inside the loopback memory
+    fn range(addr: usize, len: usize) -> Result<core::ops::Range<usize>, BusError> {
+        let start = addr.checked_sub(LOOPBACK_BASE).ok_or(BusError::Rejected)?;
+        let end = start.checked_add(len).ok_or(BusError::Rejected)?;
+        if end > LOOPBACK_MEMORY {
+            return Err(BusError::Rejected);
+        }
+        Ok(start..end)
+    }
+}
+
+impl MemoryTransport for LoopbackTransport {
+    fn remote_allocate(&self, device: DeviceId, size: usize) -> Result<usize, BusError> {
+        self.route(device)?;
+        let chunks = size.div_ceil(LOOPBACK_CHUNK).max(1);
+        if chunks > LOOPBACK_CHUNKS {
+            return Err(BusError::Rejected);
+        }
+
+        let mut state = self.state.lock();
+        let first = (0..=LOOPBACK_CHUNKS - chunks)
+            .find(|&first| state.used[first..first + chunks].iter().all(|used| !used))
+            .ok_or(BusError::Rejected)?;
+
+        state.used[first..first + chunks].fill(true);
+        state.blocks[first] = chunks as u16;
+        Ok(LOOPBACK_BASE + first * LOOPBACK_CHUNK)
+    }
+
+    fn remote_read(&self, device: DeviceId, addr: usize, buf: &mut [u8]) -> Result<(), BusError> {
+        self.route(device)?;
+        let range = Self::range(addr, buf.len())?;
+        buf.copy_from_slice(&self.state.lock().memory[range]);
+        Ok(())
+    }
+
+    fn remote_write(&self, device: DeviceId, addr: usize, data: &[u8]) -> Result<(), BusError> {
+        self.route(device)?;
+        let range = Self::range(addr, data.len())?;
+        self.state.lock().memory[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn remote_free(&self, device: DeviceId, addr: usize, size: usize) -> Result<(), BusError> {
+        self.route(device)?;
+        let range = Self::range(addr, size)?;
+        if range.start % LOOPBACK_CHUNK != 0 {
+            return Err(BusError::Rejected);
+        }
+
+        let mut state = self.state.lock();
+        let first = range.start / LOOPBACK_CHUNK;
+        let chunks = state.blocks[first] as usize;
+        if chunks == 0 || chunks != size.div_ceil(LOOPBACK_CHUNK).max(1) {
+            return Err(BusError::Rejected);
+        }
+
+        state.blocks[first] = 0;
+        state.used[first..first + chunks].fill(false);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static PEER: LoopbackTransport = LoopbackTransport::new(3);
+
+    #[test]
+    fn test_loopback_roundtrip() {
+        let a = PEER.remote_allocate(3, 1000).unwrap();
+        let b = PEER.remote_allocate(3, 10).unwrap();
+        assert_eq!(b - a, 4 * LOOPBACK_CHUNK);
+
+        PEER.remote_write(3, a + 10, b"aether").unwrap();
+        let mut buf = [0u8; 6];
+        PEER.remote_read(3, a + 10, &mut buf).unwrap();
+        assert_eq!(&buf, b"aether");
+
+        PEER.remote_free(3, a, 1000).unwrap();
+        assert_eq!(PEER.remote_free(3, a, 1000), Err(BusError::Rejected));
+        assert_eq!(PEER.remote_allocate(3, 512).unwrap(), a);
+    }
+
+    #[test]
+    fn test_loopback_rejects_bad_requests() {
+        let peer = &PEER;
+        assert_eq!(peer.remote_allocate(4, 16), Err(BusError::Unreachable));
+        assert_eq!(peer.remote_allocate(3, LOOPBACK_MEMORY + 1), Err(BusError::Rejected));
+
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            peer.remote_read(3, LOOPBACK_BASE + LOOPBACK_MEMORY - 4, &mut buf),
+            Err(BusError::Rejected)
+        );
+        assert_eq!(peer.remote_write(3, 0x10, &buf), Err(BusError::Rejected));
+    }
+}
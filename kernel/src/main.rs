@@ -1,23 +1,32 @@
 //! AetherOS Quantum Microkernel v1.0
 //! Complete implementation with all subsystems
 
-#![no_std]
-#![no_main]
-#![feature(asm_const)]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
+
+extern crate alloc;
 
 mod memory;
 mod scheduler;
 mod bus;
 mod oracle;
 
+#[cfg(not(test))]
 use core::panic::PanicInfo;
-use memory::smme::SymbianModernMemoryEngine;
-use scheduler::{ActiveObjectScheduler, Message};
+use memory::capability::Capability;
+use memory::stats::MemoryStatsSnapshot;
+use core::sync::atomic::{AtomicU8, Ordering};
+use memory::smme::{MemoryPressure, PoolId, SpillEvent, SpillPolicy, SymbianModernMemoryEngine};
+use scheduler::{ActiveObjectScheduler, Message, MessageHandler, SchedulerContext, MSG_MEMORY_PRESSURE};
 use bus::DeviceMesh;
 use oracle::TinyMLPredictor;
 
-/// Global SMME instance
-static mut SMME: SymbianModernMemoryEngine = SymbianModernMemoryEngine::new(1 << 30);
+/// Global SMME instance, also backing `alloc` collections
+#[cfg_attr(not(test), global_allocator)]
+static mut SMME: SymbianModernMemoryEngine = match SymbianModernMemoryEngine::new(1 << 30) {
+    Ok(smme) => smme,
+    Err(_) => panic!("default SMME layout needs more RAM"),
+};
 
 /// Global Scheduler instance
 static mut SCHEDULER: ActiveObjectScheduler = ActiveObjectScheduler::new();
@@ -39,49 +48,112 @@ pub extern "C" fn kernel_main() -> ! {
     }
 }
 
-fn kernel_init() {
-    unsafe {
-        // 1. Initialize SMME
-        match SMME.allocate(1 << 20) {
-            Ok(addr) => {
-                // Successfully allocated 1MB for kernel data
-                ORACLE.record_allocation(1 << 20);
-            }
-            Err(_) => {
-                // Handle allocation failure
+/// One bit per pool that spilled into a larger one since the last tick
+static SPILLED: AtomicU8 = AtomicU8::new(0);
+
+/// SMME spill hook; runs inside the allocator, so it only leaves a note for `kernel_tick`
+fn spill_warning(event: SpillEvent) {
+    SPILLED.fetch_or(1 << event.from as u8, Ordering::AcqRel);
+}
+
+/// High priority system task: answers critical memory pressure with an emergency cleanup
+struct SystemTask;
+
+impl MessageHandler for SystemTask {
+    fn handle(&mut self, msg: Message, _ctx: &mut SchedulerContext) {
+        if msg.id == MSG_MEMORY_PRESSURE && msg.data == MemoryPressure::Critical as u64 {
+            unsafe {
+                SMME.emergency_cleanup();
             }
         }
+    }
+}
+
+/// Normal priority task; placeholder until applications are loaded
+struct IdleTask;
+
+impl MessageHandler for IdleTask {
+    fn handle(&mut self, _msg: Message, _ctx: &mut SchedulerContext) {}
+}
+
+static mut SYSTEM_TASK: SystemTask = SystemTask;
+static mut IDLE_TASK: IdleTask = IdleTask;
+
+/// Boot-time entropy for the capability tag key: the free-running counter,
+/// mixed with where the bootloader put the stack
+fn boot_entropy() -> u64 {
+    #[cfg(all(target_arch = "aarch64", target_os = "none"))]
+    let counter = unsafe {
+        let counter: u64;
+        core::arch::asm!("mrs {0}, cntpct_el0", out(reg) counter);
+        counter
+    };
+    #[cfg(not(all(target_arch = "aarch64", target_os = "none")))]
+    let counter = 0x9E37_79B9_7F4A_7C15u64;
+
+    let stack = &counter as *const u64 as u64;
+    counter.rotate_left(32) ^ stack.wrapping_mul(0xBF58_476D_1CE4_E5B9)
+}
+
+fn kernel_init() {
+    unsafe {
+        // 1. Initialize SMME; the tag key must be in place before the first capability
+        SMME.set_tag_key(boot_entropy());
+        SMME.set_spill_handler(spill_warning);
+        SMME.set_spill_policy(SpillPolicy::Warn);
         
-        // 2. Initialize Scheduler
-        let _ = SCHEDULER.create_object(10); // High priority system task
-        let _ = SCHEDULER.create_object(5);  // Normal priority task
+        // 2. Initialize Scheduler; background objects gain a level every 16 dispatches they wait
+        SCHEDULER.set_aging_interval(16);
+        if let Ok(system) = SCHEDULER.create_object(10, &mut *core::ptr::addr_of_mut!(SYSTEM_TASK)) {
+            let _ = SCHEDULER.subscribe_memory_pressure(system);
+        }
+        let _ = SCHEDULER.create_object(5, &mut *core::ptr::addr_of_mut!(IDLE_TASK));
         
         // 3. Discover devices in mesh
         DEVICE_MESH.discover();
         
-        // 4. Initialize Oracle predictions
+        // 4. Initialize Oracle predictions from the allocations made so far
+        ORACLE.observe(&*core::ptr::addr_of!(SMME));
         let predicted = ORACLE.predict_next_size();
         // Pre-allocate based on prediction
-        let _ = SMME.allocate(predicted);
+        SMME.refill_reservoir(predicted);
     }
 }
 
 fn kernel_tick() {
     unsafe {
-        // 1. Schedule active objects
+        // 1. Complete timers that ran out, then schedule active objects
+        SCHEDULER.tick();
         SCHEDULER.schedule();
         
-        // 2. Check memory pressure and cleanup if needed
+        // 2. Tell subscribers when memory pressure changes or a pool overflowed, then cleanup if needed
+        if let Some(level) = SMME.poll_pressure() {
+            SCHEDULER.notify_memory_pressure(level);
+        }
+        let spilled = SPILLED.swap(0, Ordering::AcqRel);
+        for pool in [PoolId::L0, PoolId::L1, PoolId::L2] {
+            if spilled & (1 << pool as u8) != 0 {
+                SCHEDULER.notify_pool_spilled(pool);
+            }
+        }
+        
         let stats = SMME.stats();
         let utilization = (stats.total_committed * 100) / (1 << 30);
         
         if utilization > 80 {
-            let freed = SMME.predictive_cleanup();
-            // Log cleanup results
+            // Reclaims what it can; critical pressure is handled by the system task
+            let _ = SMME.predictive_cleanup();
         }
         
-        // 3. Update Oracle with current state
-        ORACLE.record_allocation(stats.total_committed);
+        // 3. Learn from recent allocations, then keep the reservoir stocked for the predicted size class
+        ORACLE.observe(&*core::ptr::addr_of!(SMME));
+        SMME.refill_reservoir(ORACLE.predict_next_size());
+
+        // Debug builds: catch heap overruns close to when they happen
+        #[cfg(feature = "smme-debug")]
+        if let Some(corruption) = SMME.check_redzones() {
+            panic!("{}", corruption);
+        }
         
         // 4. Check for distributed opportunities
         if ORACLE.should_distribute(stats.total_committed) {
@@ -94,6 +166,7 @@ fn kernel_tick() {
     }
 }
 
+#[cfg(not(test))]
 #[panic_handler]
 fn panic(_info: &PanicInfo) -> ! {
     // In real impl: Log panic info via UART
@@ -102,30 +175,76 @@ fn panic(_info: &PanicInfo) -> ! {
 
 // Kernel API exports
 #[no_mangle]
-pub extern "C" fn aether_allocate(size: usize) -> usize {
+pub extern "C" fn aether_allocate(size: usize) -> Capability {
     unsafe {
         match SMME.allocate(size) {
-            Ok(addr) => addr,
-            Err(_) => 0,
+            Ok(cap) => cap,
+            Err(_) => Capability::null(),
         }
     }
 }
 
+/// Aligned allocation for DMA buffers and page-table frames; `align` must be a power of two
 #[no_mangle]
-pub extern "C" fn aether_get_memory_stats() -> (usize, usize) {
+pub extern "C" fn aether_allocate_aligned(size: usize, align: usize) -> Capability {
     unsafe {
-        let stats = SMME.stats();
-        (stats.total_reserved, stats.total_committed)
+        match SMME.allocate_aligned(size, align) {
+            Ok(cap) => cap,
+            Err(_) => Capability::null(),
+        }
     }
 }
 
+#[no_mangle]
+pub extern "C" fn aether_allocate_for(owner: u32, size: usize) -> Capability {
+    unsafe {
+        match SMME.allocate_for(owner, size) {
+            Ok(cap) => cap,
+            Err(_) => Capability::null(),
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn aether_free_for(owner: u32, cap: Capability) -> bool {
+    unsafe { SMME.free_for(owner, cap).is_ok() }
+}
+
+/// Apply an application's declared `@memory(budget: ...)`
+#[no_mangle]
+pub extern "C" fn aether_set_memory_budget(owner: u32, budget: usize) -> bool {
+    unsafe { SMME.set_quota(owner, budget).is_ok() }
+}
+
+#[no_mangle]
+pub extern "C" fn aether_free(cap: Capability) -> bool {
+    unsafe { SMME.free(cap).is_ok() }
+}
+
+/// Per-pool statistics for monitoring agents; check `version` before reading
+#[no_mangle]
+pub extern "C" fn aether_get_memory_stats() -> MemoryStatsSnapshot {
+    unsafe { SMME.snapshot() }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Once;
+
+    /// Tests share the kernel statics, so boot them only once.
+    /// The default pools are not host memory, so SMME is rebuilt over memory that is.
+    fn boot() {
+        static BOOT: Once = Once::new();
+        BOOT.call_once(|| {
+            unsafe { SMME = SymbianModernMemoryEngine::host_backed() };
+            kernel_init();
+        });
+    }
 
     #[test]
     fn test_kernel_init() {
-        kernel_init();
+        boot();
         
         unsafe {
             let stats = SMME.stats();
@@ -140,12 +259,16 @@ mod tests {
 
     #[test]
     fn test_kernel_api() {
-        kernel_init();
+        boot();
         
-        let addr = aether_allocate(4096);
-        assert!(addr > 0);
+        let cap = aether_allocate(4096);
+        assert!(!cap.is_null() && cap.addr() > 0);
         
-        let (reserved, committed) = aether_get_memory_stats();
-        assert!(committed >= 4096);
+        let stats = aether_get_memory_stats();
+        assert!(stats.total_committed >= 4096);
+        assert!(stats.pools[0].allocations >= 1);
+
+        assert!(aether_free(cap));
+        assert!(!aether_free(cap));
     }
 }
@@ -0,0 +1,181 @@
+//! Buddy Allocator - power-of-two placement for the L2 large pool
+//! Works in pool granules; the pool itself still does the accounting
+
+use super::spin::SpinLock;
+
+/// Largest block is 2^MAX_ORDER granules
+pub const MAX_ORDER: usize = 10;
+const FREE_WORDS: usize = (1 << MAX_ORDER) / 64;
+
+struct BuddyState {
+    // free[order] has one bit per block of that order
+    free: [[u64; FREE_WORDS]; MAX_ORDER + 1],
+    free_blocks: [usize; MAX_ORDER + 1],
+    splits: usize,
+    merges: usize,
+}
+
+impl BuddyState {
+    const fn new() -> Self {
+        Self {
+            free: [[0; FREE_WORDS]; MAX_ORDER + 1],
+            free_blocks: [0; MAX_ORDER + 1],
+            splits: 0,
+            merges: 0,
+        }
+    }
+
+    fn is_free(&self, order: usize, block: usize) -> bool {
+        self.free[order][block / 64] & (1 << (block % 64)) != 0
+    }
+
+    fn set_free(&mut self, order: usize, block: usize, free: bool) {
+        if free {
+            self.free[order][block / 64] |= 1 << (block % 64);
+            self.free_blocks[order] += 1;
+        } else {
+            self.free[order][block / 64] &= !(1 << (block % 64));
+            self.free_blocks[order] -= 1;
+        }
+    }
+
+    fn first_free(&self, order: usize) -> Option<usize> {
+        self.free[order]
+            .iter()
+            .position(|&w| w != 0)
+            .map(|w| w * 64 + self.free[order][w].trailing_zeros() as usize)
+    }
+}
+
+pub struct BuddyAllocator {
+    state: SpinLock<BuddyState>,
+}
+
+impl BuddyAllocator {
+    pub const fn new() -> Self {
+        Self {
+            state: SpinLock::new(BuddyState::new()),
+        }
+    }
+
+    /// Order of the smallest block holding `granules`
+    pub fn order_for(granules: usize) -> usize {
+        granules.max(1).next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// Start over with `granules` free granules, split into maximal aligned blocks
+    pub fn reset(&self, granules: usize) {
+        let mut state = self.state.lock();
+        *state = BuddyState::new();
+
+        let granules = granules.min(1 << MAX_ORDER);
+        let mut start = 0;
+        while start < granules {
+            let mut order = MAX_ORDER;
+            while start % (1 << order) != 0 || start + (1 << order) > granules {
+                order -= 1;
+            }
+            state.set_free(order, start >> order, true);
+            start += 1 << order;
+        }
+    }
+
+    /// Take a block of `order`, returning its first granule
+    pub fn allocate(&self, order: usize) -> Option<usize> {
+        let mut state = self.state.lock();
+
+        let mut current = (order..=MAX_ORDER).find(|&o| state.free_blocks[o] > 0)?;
+        let mut block = state.first_free(current)?;
+        state.set_free(current, block, false);
+
+        // Split down, keeping the upper halves free
+        while current > order {
+            current -= 1;
+            block *= 2;
+            state.set_free(current, block + 1, true);
+            state.splits += 1;
+        }
+
+        Some(block << order)
+    }
+
+    /// Return a block, merging with its buddy while the buddy is free
+    pub fn free(&self, first: usize, order: usize) {
+        let mut state = self.state.lock();
+
+        let mut order = order;
+        let mut block = first >> order;
+        while order < MAX_ORDER && state.is_free(order, block ^ 1) {
+            state.set_free(order, block ^ 1, false);
+            block /= 2;
+            order += 1;
+            state.merges += 1;
+        }
+        state.set_free(order, block, true);
+    }
+
+    pub fn stats(&self) -> BuddyStats {
+        let state = self.state.lock();
+        let largest = (0..=MAX_ORDER).rev().find(|&o| state.free_blocks[o] > 0);
+
+        BuddyStats {
+            splits: state.splits,
+            merges: state.merges,
+            largest_free_granules: largest.map_or(0, |o| 1 << o),
+            free_granules: (0..=MAX_ORDER).map(|o| state.free_blocks[o] << o).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BuddyStats {
+    pub splits: usize,
+    pub merges: usize,
+    pub largest_free_granules: usize,
+    pub free_granules: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_merge() {
+        let buddy = BuddyAllocator::new();
+        buddy.reset(1024);
+
+        let a = buddy.allocate(0).unwrap();
+        let b = buddy.allocate(0).unwrap();
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(buddy.stats().splits, 10);
+        assert_eq!(buddy.stats().largest_free_granules, 512);
+
+        buddy.free(a, 0);
+        buddy.free(b, 0);
+        let stats = buddy.stats();
+        assert_eq!(stats.merges, 10);
+        assert_eq!(stats.largest_free_granules, 1024);
+    }
+
+    #[test]
+    fn test_blocks_are_naturally_aligned() {
+        let buddy = BuddyAllocator::new();
+        buddy.reset(1024);
+
+        let _ = buddy.allocate(0).unwrap();
+        let big = buddy.allocate(3).unwrap();
+        assert_eq!(big % 8, 0);
+        assert_eq!(buddy.stats().free_granules, 1024 - 1 - 8);
+    }
+
+    #[test]
+    fn test_uneven_pool_size() {
+        let buddy = BuddyAllocator::new();
+        buddy.reset(768);
+
+        let stats = buddy.stats();
+        assert_eq!(stats.free_granules, 768);
+        assert_eq!(stats.largest_free_granules, 512);
+        assert!(buddy.allocate(MAX_ORDER).is_none());
+    }
+}
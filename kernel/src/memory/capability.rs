@@ -0,0 +1,331 @@
+//! Trust Root - capability handles and memory tagging for SMME (BlackBerry DNA)
+//! Every allocation is reached through a tagged, generation-checked capability
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::quota::{OwnerId, KERNEL_OWNER};
+use super::smme::AllocationError;
+use super::spin::SpinLock;
+
+/// Live capabilities tracked at once
+pub const MAX_CAPABILITIES: usize = 512;
+
+/// Access rights carried by a capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Rights(u8);
+
+impl Rights {
+    pub const NONE: Rights = Rights(0);
+    pub const READ: Rights = Rights(1);
+    pub const WRITE: Rights = Rights(2);
+    pub const READ_WRITE: Rights = Rights(3);
+
+    pub const fn contains(self, other: Rights) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+/// Unforgeable handle to an allocation.
+/// Only SMME can mint one; the tag binds every field to the issuing engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct Capability {
+    addr: usize,
+    len: usize,
+    align: usize,
+    owner: OwnerId,
+    slot: u32,
+    generation: u32,
+    rights: Rights,
+    tag: u64,
+}
+
+impl Capability {
+    /// Handle returned through the C API when allocation fails
+    pub const fn null() -> Self {
+        Self {
+            addr: 0,
+            len: 0,
+            align: 0,
+            owner: 0,
+            slot: u32::MAX,
+            generation: 0,
+            rights: Rights::NONE,
+            tag: 0,
+        }
+    }
+
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Alignment the allocation was made with
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn owner(&self) -> OwnerId {
+        self.owner
+    }
+
+    pub fn rights(&self) -> Rights {
+        self.rights
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.slot == u32::MAX
+    }
+}
+
+/// SplitMix64 rounds over `words`, seeded with `key`
+pub(super) fn keyed_mix(key: u64, words: &[u64]) -> u64 {
+    let mut state = key;
+    for &word in words {
+        state = state.wrapping_add(word).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        state ^= state >> 31;
+    }
+    state
+}
+
+#[derive(Clone, Copy)]
+struct CapEntry {
+    addr: usize,
+    len: usize,
+    align: usize,
+    owner: OwnerId,
+    rights: Rights,
+    generation: u32,
+    live: bool,
+}
+
+impl CapEntry {
+    const fn empty() -> Self {
+        Self {
+            addr: 0,
+            len: 0,
+            align: 0,
+            owner: 0,
+            rights: Rights::NONE,
+            generation: 0,
+            live: false,
+        }
+    }
+}
+
+pub struct CapabilityTable {
+    entries: SpinLock<[CapEntry; MAX_CAPABILITIES]>,
+    key: AtomicU64,
+}
+
+impl CapabilityTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: SpinLock::new([CapEntry::empty(); MAX_CAPABILITIES]),
+            key: AtomicU64::new(0x5DEE_CE66_D1CE_4E5B),
+        }
+    }
+
+    /// Seed the tag key from boot-time entropy. Handles issued under the old key stop
+    /// validating, so this must run before the first allocation.
+    pub fn set_key(&self, key: u64) {
+        self.key.store(key, Ordering::Release);
+    }
+
+    /// Keyed mix of every capability field
+    fn tag(&self, cap: &Capability) -> u64 {
+        keyed_mix(
+            self.key.load(Ordering::Acquire),
+            &[
+                cap.addr as u64,
+                cap.len as u64,
+                cap.align as u64,
+                cap.owner as u64,
+                ((cap.slot as u64) << 32) | cap.generation as u64,
+                cap.rights.bits() as u64,
+            ],
+        )
+    }
+
+    fn seal(&self, mut cap: Capability) -> Capability {
+        cap.tag = self.tag(&cap);
+        cap
+    }
+
+    pub fn issue(
+        &self,
+        addr: usize,
+        len: usize,
+        align: usize,
+        owner: OwnerId,
+        rights: Rights,
+    ) -> Result<Capability, AllocationError> {
+        let mut entries = self.entries.lock();
+        let slot = entries
+            .iter()
+            .position(|e| !e.live)
+            .ok_or(AllocationError::OutOfMemory)?;
+
+        let entry = &mut entries[slot];
+        *entry = CapEntry {
+            addr,
+            len,
+            align,
+            owner,
+            rights,
+            generation: entry.generation,
+            live: true,
+        };
+
+        Ok(self.seal(Capability {
+            addr,
+            len,
+            align,
+            owner,
+            slot: slot as u32,
+            generation: entry.generation,
+            rights,
+            tag: 0,
+        }))
+    }
+
+    /// Check that `cap` is genuine, still live, and lets `accessor` use `rights`
+    pub fn validate(
+        &self,
+        cap: &Capability,
+        accessor: OwnerId,
+        rights: Rights,
+    ) -> Result<(), AllocationError> {
+        let entries = self.entries.lock();
+        Self::check(&entries, cap, self.tag(cap), accessor, rights)
+    }
+
+    fn check(
+        entries: &[CapEntry; MAX_CAPABILITIES],
+        cap: &Capability,
+        tag: u64,
+        accessor: OwnerId,
+        rights: Rights,
+    ) -> Result<(), AllocationError> {
+        if cap.tag != tag {
+            // Forged or corrupted handle
+            return Err(AllocationError::AccessDenied);
+        }
+
+        let entry = entries
+            .get(cap.slot as usize)
+            .ok_or(AllocationError::InvalidAddress)?;
+        if !entry.live || entry.generation != cap.generation {
+            return Err(AllocationError::UseAfterFree);
+        }
+        // The tag is only as secret as the key; the table is the authority on what was issued
+        if (entry.addr, entry.len, entry.align, entry.owner) != (cap.addr, cap.len, cap.align, cap.owner) {
+            return Err(AllocationError::AccessDenied);
+        }
+        if accessor != cap.owner && accessor != KERNEL_OWNER {
+            return Err(AllocationError::AccessDenied);
+        }
+        if !cap.rights.contains(rights) || !entry.rights.contains(cap.rights) {
+            return Err(AllocationError::AccessDenied);
+        }
+        Ok(())
+    }
+
+    /// Derive a handle with fewer rights
+    pub fn restrict(&self, cap: &Capability, rights: Rights) -> Result<Capability, AllocationError> {
+        self.validate(cap, cap.owner, rights)?;
+        Ok(self.seal(Capability { rights, ..*cap }))
+    }
+
+    /// Invalidate every handle to the allocation; the slot's generation moves on
+    pub fn revoke(&self, cap: &Capability, accessor: OwnerId) -> Result<(), AllocationError> {
+        let tag = self.tag(cap);
+        let mut entries = self.entries.lock();
+        Self::check(&entries, cap, tag, accessor, Rights::WRITE)?;
+
+        let entry = &mut entries[cap.slot as usize];
+        entry.live = false;
+        entry.generation = entry.generation.wrapping_add(1);
+        Ok(())
+    }
+
+    pub fn live_count(&self) -> usize {
+        self.entries.lock().iter().filter(|e| e.live).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_handle_after_slot_reuse() {
+        let table = CapabilityTable::new();
+        let old = table.issue(0x1000, 64, 1, 1, Rights::READ_WRITE).unwrap();
+        table.revoke(&old, 1).unwrap();
+
+        let new = table.issue(0x1000, 64, 1, 1, Rights::READ_WRITE).unwrap();
+        assert!(matches!(table.validate(&old, 1, Rights::READ), Err(AllocationError::UseAfterFree)));
+        assert!(table.validate(&new, 1, Rights::READ_WRITE).is_ok());
+    }
+
+    #[test]
+    fn test_forged_and_cross_owner_handles() {
+        let table = CapabilityTable::new();
+        let cap = table.issue(0x2000, 128, 1, 5, Rights::READ_WRITE).unwrap();
+
+        let mut forged = cap;
+        forged.len = 1 << 20;
+        assert!(matches!(table.validate(&forged, 5, Rights::READ), Err(AllocationError::AccessDenied)));
+
+        assert!(matches!(table.validate(&cap, 6, Rights::READ), Err(AllocationError::AccessDenied)));
+        assert!(table.validate(&cap, KERNEL_OWNER, Rights::READ).is_ok());
+    }
+
+    #[test]
+    fn test_forged_handle_with_valid_tag() {
+        let table = CapabilityTable::new();
+        table.set_key(0x0123_4567_89AB_CDEF);
+        let cap = table.issue(0x4000, 256, 16, 5, Rights::READ_WRITE).unwrap();
+
+        // Someone who learned the key can tag anything, but not change what the slot covers
+        let forgeries = [
+            Capability { len: 1 << 20, ..cap },
+            Capability { addr: 0x8000, ..cap },
+            Capability { align: 1, ..cap },
+            Capability { owner: 6, ..cap },
+        ];
+        for forged in forgeries {
+            let forged = table.seal(forged);
+            assert!(matches!(table.validate(&forged, KERNEL_OWNER, Rights::READ), Err(AllocationError::AccessDenied)));
+        }
+        assert!(table.validate(&cap, 5, Rights::READ_WRITE).is_ok());
+    }
+
+    #[test]
+    fn test_restricted_rights() {
+        let table = CapabilityTable::new();
+        let cap = table.issue(0x3000, 4096, 1, 2, Rights::READ_WRITE).unwrap();
+        let read_only = table.restrict(&cap, Rights::READ).unwrap();
+
+        assert!(table.validate(&read_only, 2, Rights::READ).is_ok());
+        assert!(table.validate(&read_only, 2, Rights::WRITE).is_err());
+        // A read-only handle cannot free the allocation
+        assert!(table.revoke(&read_only, 2).is_err());
+        assert_eq!(table.live_count(), 1);
+    }
+}
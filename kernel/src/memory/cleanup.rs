@@ -0,0 +1,108 @@
+//! Reclamation registries used by SMME cleanup (Symbian DNA)
+//! Purgeable caches can be decommitted at will; low-memory handlers are asked to shed memory
+
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use super::smme::AllocationError;
+use super::spin::SpinLock;
+
+/// Purgeable ranges tracked at once
+pub const MAX_PURGEABLE: usize = 32;
+/// Low-memory handlers that can be registered
+pub const MAX_LOW_MEMORY_HANDLERS: usize = 8;
+
+/// Ranges whose contents the owner can rebuild, so SMME may drop their pages
+pub struct PurgeableRegistry {
+    // (addr, size); addr zero marks an empty slot
+    ranges: SpinLock<[(usize, usize); MAX_PURGEABLE]>,
+}
+
+impl PurgeableRegistry {
+    pub const fn new() -> Self {
+        Self {
+            ranges: SpinLock::new([(0, 0); MAX_PURGEABLE]),
+        }
+    }
+
+    pub fn register(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
+        let mut ranges = self.ranges.lock();
+        if ranges.iter().any(|&(a, _)| a == addr) {
+            return Err(AllocationError::InvalidRequest);
+        }
+        let slot = ranges
+            .iter_mut()
+            .find(|(a, _)| *a == 0)
+            .ok_or(AllocationError::OutOfMemory)?;
+        *slot = (addr, size);
+        Ok(())
+    }
+
+    /// Forget a range; returns false if it wasn't registered
+    pub fn remove(&self, addr: usize) -> bool {
+        let mut ranges = self.ranges.lock();
+        match ranges.iter_mut().find(|(a, _)| *a == addr) {
+            Some(slot) => {
+                *slot = (0, 0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn snapshot(&self) -> [(usize, usize); MAX_PURGEABLE] {
+        *self.ranges.lock()
+    }
+}
+
+/// Owner callbacks invoked when memory runs low; the argument is the number of bytes wanted
+pub struct LowMemoryHandlers {
+    handlers: [AtomicPtr<()>; MAX_LOW_MEMORY_HANDLERS],
+}
+
+impl LowMemoryHandlers {
+    pub const fn new() -> Self {
+        Self {
+            handlers: [const { AtomicPtr::new(ptr::null_mut()) }; MAX_LOW_MEMORY_HANDLERS],
+        }
+    }
+
+    /// Returns the slot to pass to `unregister`
+    pub fn register(&self, handler: fn(usize)) -> Result<usize, AllocationError> {
+        self.handlers
+            .iter()
+            .position(|slot| {
+                slot.compare_exchange(
+                    ptr::null_mut(),
+                    handler as *mut (),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            })
+            .ok_or(AllocationError::OutOfMemory)
+    }
+
+    pub fn unregister(&self, slot: usize) {
+        if let Some(handler) = self.handlers.get(slot) {
+            handler.store(ptr::null_mut(), Ordering::Release);
+        }
+    }
+
+    /// Call every handler with the number of bytes still wanted, stopping once `freed` says enough
+    pub fn notify(&self, wanted: usize, freed: impl Fn() -> usize) {
+        for slot in &self.handlers {
+            let handler = slot.load(Ordering::Acquire);
+            if handler.is_null() {
+                continue;
+            }
+            let so_far = freed();
+            if so_far >= wanted {
+                break;
+            }
+            // Only ever set from a `fn(usize)` in `register`
+            let handler: fn(usize) = unsafe { core::mem::transmute(handler) };
+            handler(wanted - so_far);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Pool Layout - derive SMME pool placement from the boot memory map
+
+/// Size of the L0 fast pool
+pub const L0_POOL_SIZE: usize = 64 * 1024;
+/// Size of the L1 general pool
+pub const L1_POOL_SIZE: usize = 2 * 1024 * 1024;
+/// Upper bound for the L2 large pool
+pub const L2_POOL_MAX: usize = 64 * 1024 * 1024;
+/// Smallest L2 pool worth booting with
+pub const L2_POOL_MIN: usize = 2 * 1024 * 1024;
+/// Every pool base is aligned to this
+pub const POOL_ALIGN: usize = 64 * 1024;
+/// Largest memory map accepted from the bootloader
+pub const MAX_MEMORY_REGIONS: usize = 16;
+
+/// A usable physical RAM range reported by the device tree or bootloader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub base: usize,
+    pub size: usize,
+}
+
+impl MemoryRegion {
+    pub const fn new(base: usize, size: usize) -> Self {
+        Self { base, size }
+    }
+
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    fn overlaps(&self, other: &MemoryRegion) -> bool {
+        self.base < other.end() && other.base < self.end()
+    }
+
+    fn contains(&self, other: &MemoryRegion) -> bool {
+        other.base >= self.base && other.end() <= self.end()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutError {
+    TooManyRegions,
+    /// A region is empty, wraps the address space or overlaps another region
+    InvalidRegion,
+    /// Not enough usable memory for every pool
+    InsufficientMemory,
+    /// Pools overlap each other or fall outside the memory map
+    Overlap,
+    /// Pools add up to more than `total_ram`
+    ExceedsTotalRam,
+}
+
+/// Placement of the three SMME pools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolLayout {
+    pub l0: MemoryRegion,
+    pub l1: MemoryRegion,
+    pub l2: MemoryRegion,
+}
+
+impl PoolLayout {
+    /// Carve L0 and L1 from the lowest region with room, then give L2 the largest remaining span
+    pub fn from_memory_map(total_ram: usize, regions: &[MemoryRegion]) -> Result<Self, LayoutError> {
+        if regions.len() > MAX_MEMORY_REGIONS {
+            return Err(LayoutError::TooManyRegions);
+        }
+        for (i, region) in regions.iter().enumerate() {
+            if region.size == 0 || region.base.checked_add(region.size).is_none() {
+                return Err(LayoutError::InvalidRegion);
+            }
+            if regions[..i].iter().any(|other| other.overlaps(region)) {
+                return Err(LayoutError::InvalidRegion);
+            }
+        }
+
+        // Remaining span of each region, shrunk from the front as pools are carved
+        let mut free = [MemoryRegion::new(0, 0); MAX_MEMORY_REGIONS];
+        free[..regions.len()].copy_from_slice(regions);
+        let free = &mut free[..regions.len()];
+
+        let l0 = Self::carve_lowest(free, L0_POOL_SIZE)?;
+        let l1 = Self::carve_lowest(free, L1_POOL_SIZE)?;
+
+        let budget = total_ram.saturating_sub(L0_POOL_SIZE + L1_POOL_SIZE);
+        let (idx, available) = free
+            .iter()
+            .map(Self::aligned_space)
+            .enumerate()
+            .max_by_key(|&(_, space)| space)
+            .ok_or(LayoutError::InsufficientMemory)?;
+        let l2_size = (available.min(budget).min(L2_POOL_MAX) / POOL_ALIGN) * POOL_ALIGN;
+        if l2_size < L2_POOL_MIN {
+            return Err(LayoutError::InsufficientMemory);
+        }
+        let l2 = Self::carve(&mut free[idx], l2_size);
+
+        let layout = Self { l0, l1, l2 };
+        layout.validate(total_ram, regions)?;
+        Ok(layout)
+    }
+
+    /// Check that pools are disjoint, backed by usable memory and fit in `total_ram`
+    pub fn validate(&self, total_ram: usize, regions: &[MemoryRegion]) -> Result<(), LayoutError> {
+        let pools = [self.l0, self.l1, self.l2];
+
+        let mut total: usize = 0;
+        for (i, pool) in pools.iter().enumerate() {
+            if pool.size == 0 || pool.base.checked_add(pool.size).is_none() {
+                return Err(LayoutError::InvalidRegion);
+            }
+            if pools[..i].iter().any(|other| other.overlaps(pool)) {
+                return Err(LayoutError::Overlap);
+            }
+            if !regions.iter().any(|region| region.contains(pool)) {
+                return Err(LayoutError::Overlap);
+            }
+            total = total.saturating_add(pool.size);
+        }
+
+        if total > total_ram {
+            return Err(LayoutError::ExceedsTotalRam);
+        }
+        Ok(())
+    }
+
+    fn aligned_space(region: &MemoryRegion) -> usize {
+        let start = region.base.next_multiple_of(POOL_ALIGN);
+        region.end().saturating_sub(start)
+    }
+
+    fn carve_lowest(free: &mut [MemoryRegion], size: usize) -> Result<MemoryRegion, LayoutError> {
+        let region = free
+            .iter_mut()
+            .filter(|region| Self::aligned_space(region) >= size)
+            .min_by_key(|region| region.base)
+            .ok_or(LayoutError::InsufficientMemory)?;
+        Ok(Self::carve(region, size))
+    }
+
+    fn carve(region: &mut MemoryRegion, size: usize) -> MemoryRegion {
+        let base = region.base.next_multiple_of(POOL_ALIGN);
+        let end = region.end();
+        region.base = base + size;
+        region.size = end - region.base;
+        MemoryRegion::new(base, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_from_single_region() {
+        let regions = [MemoryRegion::new(0x4000_0000, 128 * 1024 * 1024)];
+        let layout = PoolLayout::from_memory_map(128 * 1024 * 1024, &regions).unwrap();
+
+        assert_eq!(layout.l0.base, 0x4000_0000);
+        assert_eq!(layout.l1.base, 0x4001_0000);
+        assert_eq!(layout.l2.base, 0x4021_0000);
+        assert_eq!(layout.l2.size, L2_POOL_MAX);
+    }
+
+    #[test]
+    fn test_layout_small_board_and_holes() {
+        // 8MB board with a firmware hole at the start
+        let regions = [
+            MemoryRegion::new(0x8_1234, 0x8000),
+            MemoryRegion::new(0x20_0000, 6 * 1024 * 1024),
+        ];
+        let layout = PoolLayout::from_memory_map(8 * 1024 * 1024, &regions).unwrap();
+
+        assert!(layout.l0.base >= 0x20_0000);
+        assert_eq!(layout.l2.size, 6 * 1024 * 1024 - L0_POOL_SIZE - L1_POOL_SIZE);
+        assert!(layout.validate(8 * 1024 * 1024, &regions).is_ok());
+    }
+
+    #[test]
+    fn test_layout_validation_errors() {
+        let overlapping = [
+            MemoryRegion::new(0x1000_0000, 0x100_0000),
+            MemoryRegion::new(0x1080_0000, 0x100_0000),
+        ];
+        assert_eq!(
+            PoolLayout::from_memory_map(1 << 30, &overlapping),
+            Err(LayoutError::InvalidRegion)
+        );
+
+        let tiny = [MemoryRegion::new(0x1000_0000, 1024 * 1024)];
+        assert_eq!(
+            PoolLayout::from_memory_map(1 << 30, &tiny),
+            Err(LayoutError::InsufficientMemory)
+        );
+
+        let regions = [MemoryRegion::new(0x1000_0000, 64 * 1024 * 1024)];
+        let mut layout = PoolLayout::from_memory_map(1 << 30, &regions).unwrap();
+        assert_eq!(layout.validate(4 * 1024 * 1024, &regions), Err(LayoutError::ExceedsTotalRam));
+
+        layout.l1.base = layout.l0.base;
+        assert_eq!(layout.validate(1 << 30, &regions), Err(LayoutError::Overlap));
+    }
+}
@@ -1 +1,15 @@
 pub mod smme;
+pub mod slab;
+pub mod buddy;
+pub mod capability;
+pub mod cleanup;
+pub mod layout;
+pub mod paging;
+pub mod quota;
+#[cfg(feature = "smme-debug")]
+pub mod redzone;
+pub mod remote;
+pub mod reservoir;
+pub mod shared;
+pub mod spin;
+pub mod stats;
@@ -0,0 +1,419 @@
+//! Virtual memory - AArch64 stage 1 translation tables (4KB granule, 48-bit addresses)
+//! Tables are built through a `FrameAllocator`, so the walk runs the same on the host
+
+use super::smme::PAGE_SIZE;
+
+/// Descriptors per table
+pub const ENTRIES: usize = 512;
+/// Translation levels for a 48-bit address with a 4KB granule
+const LEVELS: usize = 4;
+/// Size of the address space a single table root covers
+pub const VA_LIMIT: usize = 1 << 48;
+
+// Descriptor bits
+const VALID: u64 = 1 << 0;
+// Table descriptor at levels 0-2, page descriptor at level 3
+const TABLE_OR_PAGE: u64 = 1 << 1;
+// AttrIndx 0: normal write-back memory in MAIR_EL1
+const ATTR_NORMAL: u64 = 0 << 2;
+const AP_EL0: u64 = 1 << 6;
+const AP_READ_ONLY: u64 = 1 << 7;
+const SH_INNER: u64 = 0b11 << 8;
+const ACCESS_FLAG: u64 = 1 << 10;
+const NOT_GLOBAL: u64 = 1 << 11;
+const PXN: u64 = 1 << 53;
+const UXN: u64 = 1 << 54;
+const ADDR_MASK: u64 = 0x0000_FFFF_FFFF_F000;
+
+/// Permissions of a mapped page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Access(u8);
+
+impl Access {
+    pub const READ: Access = Access(1);
+    pub const WRITE: Access = Access(2);
+    pub const EXECUTE: Access = Access(4);
+    /// Reachable from EL0, not just the kernel
+    pub const USER: Access = Access(8);
+    pub const READ_WRITE: Access = Access(3);
+
+    pub const fn union(self, other: Access) -> Access {
+        Access(self.0 | other.0)
+    }
+
+    pub const fn contains(self, other: Access) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn descriptor(self) -> u64 {
+        let mut desc = VALID | TABLE_OR_PAGE | ATTR_NORMAL | SH_INNER | ACCESS_FLAG;
+        if !self.contains(Access::WRITE) {
+            desc |= AP_READ_ONLY;
+        }
+        if self.contains(Access::USER) {
+            // The kernel never runs user pages
+            desc |= AP_EL0 | NOT_GLOBAL | PXN;
+            if !self.contains(Access::EXECUTE) {
+                desc |= UXN;
+            }
+        } else {
+            desc |= UXN;
+            if !self.contains(Access::EXECUTE) {
+                desc |= PXN;
+            }
+        }
+        desc
+    }
+
+    fn from_descriptor(desc: u64) -> Access {
+        let mut access = Access::READ;
+        if desc & AP_READ_ONLY == 0 {
+            access = access.union(Access::WRITE);
+        }
+        let user = desc & AP_EL0 != 0;
+        if user {
+            access = access.union(Access::USER);
+        }
+        let never = if user { UXN } else { PXN };
+        if desc & never == 0 {
+            access = access.union(Access::EXECUTE);
+        }
+        access
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagingError {
+    /// An address or length is not a multiple of `PAGE_SIZE`
+    Misaligned,
+    /// Outside the 48-bit address space
+    InvalidAddress,
+    /// Pages must at least be readable
+    InvalidAccess,
+    /// No frame left for a translation table
+    OutOfFrames,
+    /// The page already maps something else
+    AlreadyMapped,
+    NotMapped,
+}
+
+/// Source of the 4KB frames translation tables live in
+pub trait FrameAllocator {
+    /// Physical address of a free, page-aligned frame
+    fn allocate_frame(&mut self) -> Option<usize>;
+    fn free_frame(&mut self, frame: usize);
+    /// The table stored in a frame handed out by `allocate_frame`
+    fn table(&mut self, frame: usize) -> &mut [u64; ENTRIES];
+}
+
+/// Index into the level `level` table for `va`
+fn index(va: usize, level: usize) -> usize {
+    (va >> (39 - 9 * level)) & (ENTRIES - 1)
+}
+
+/// Drop stale translations of `va` after its page descriptor changed
+fn invalidate(asid: u16, va: usize) {
+    #[cfg(all(target_arch = "aarch64", target_os = "none"))]
+    unsafe {
+        let operand = ((asid as u64) << 48) | ((va >> 12) as u64 & 0xFFF_FFFF_FFFF);
+        core::arch::asm!("dsb ishst", "tlbi vae1is, {0}", "dsb ish", "isb", in(reg) operand);
+    }
+    #[cfg(not(all(target_arch = "aarch64", target_os = "none")))]
+    let _ = (asid, va);
+}
+
+/// One set of translation tables, e.g. per active object
+pub struct AddressSpace {
+    root: usize,
+    asid: u16,
+}
+
+impl AddressSpace {
+    pub fn new<F: FrameAllocator>(frames: &mut F, asid: u16) -> Result<Self, PagingError> {
+        let root = Self::new_table(frames)?;
+        Ok(Self { root, asid })
+    }
+
+    /// Value for TTBR0_EL1 when switching to this address space
+    pub fn ttbr0(&self) -> u64 {
+        ((self.asid as u64) << 48) | self.root as u64
+    }
+
+    /// Map `len` bytes at `va` onto `pa`. Remapping a page to the same frame and
+    /// permissions is allowed; on failure nothing new is mapped.
+    pub fn map<F: FrameAllocator>(
+        &mut self,
+        frames: &mut F,
+        va: usize,
+        pa: usize,
+        len: usize,
+        access: Access,
+    ) -> Result<(), PagingError> {
+        Self::check_range(va, len)?;
+        Self::check_range(pa, len)?;
+        if !access.contains(Access::READ) {
+            return Err(PagingError::InvalidAccess);
+        }
+        let descriptor = |offset: usize| (pa + offset) as u64 | access.descriptor();
+
+        // Build every table and look for clashes before any page goes live.
+        // Tables created here stay around until the address space is destroyed.
+        for offset in (0..len).step_by(PAGE_SIZE) {
+            let table = self.walk(frames, va + offset, true)?.ok_or(PagingError::OutOfFrames)?;
+            let entry = frames.table(table)[index(va + offset, LEVELS - 1)];
+            if entry & VALID != 0 && entry != descriptor(offset) {
+                return Err(PagingError::AlreadyMapped);
+            }
+        }
+
+        for offset in (0..len).step_by(PAGE_SIZE) {
+            let table = self.walk(frames, va + offset, false)?.ok_or(PagingError::NotMapped)?;
+            frames.table(table)[index(va + offset, LEVELS - 1)] = descriptor(offset);
+        }
+        Ok(())
+    }
+
+    /// Remove the mapping of every page in the range; all of them must be mapped
+    pub fn unmap<F: FrameAllocator>(&mut self, frames: &mut F, va: usize, len: usize) -> Result<(), PagingError> {
+        self.update(frames, va, len, |_| 0)
+    }
+
+    /// Change the permissions of every page in the range, keeping their frames
+    pub fn protect<F: FrameAllocator>(
+        &mut self,
+        frames: &mut F,
+        va: usize,
+        len: usize,
+        access: Access,
+    ) -> Result<(), PagingError> {
+        if !access.contains(Access::READ) {
+            return Err(PagingError::InvalidAccess);
+        }
+        self.update(frames, va, len, |entry| (entry & ADDR_MASK) | access.descriptor())
+    }
+
+    /// Physical address and permissions `va` translates to
+    pub fn translate<F: FrameAllocator>(&self, frames: &mut F, va: usize) -> Option<(usize, Access)> {
+        if va >= VA_LIMIT {
+            return None;
+        }
+        let table = self.walk(frames, va, false).ok()??;
+        let entry = frames.table(table)[index(va, LEVELS - 1)];
+        if entry & VALID == 0 {
+            return None;
+        }
+        let pa = (entry & ADDR_MASK) as usize + va % PAGE_SIZE;
+        Some((pa, Access::from_descriptor(entry)))
+    }
+
+    /// Give every table frame back; the mapped pages themselves are not touched
+    pub fn destroy<F: FrameAllocator>(self, frames: &mut F) {
+        Self::free_tables(frames, self.root, 0);
+    }
+
+    fn new_table<F: FrameAllocator>(frames: &mut F) -> Result<usize, PagingError> {
+        let frame = frames.allocate_frame().ok_or(PagingError::OutOfFrames)?;
+        frames.table(frame).fill(0);
+        Ok(frame)
+    }
+
+    fn free_tables<F: FrameAllocator>(frames: &mut F, table: usize, level: usize) {
+        if level < LEVELS - 1 {
+            for i in 0..ENTRIES {
+                let entry = frames.table(table)[i];
+                if entry & VALID != 0 {
+                    Self::free_tables(frames, (entry & ADDR_MASK) as usize, level + 1);
+                }
+            }
+        }
+        frames.free_frame(table);
+    }
+
+    fn check_range(addr: usize, len: usize) -> Result<(), PagingError> {
+        if len == 0 || !addr.is_multiple_of(PAGE_SIZE) || !len.is_multiple_of(PAGE_SIZE) {
+            return Err(PagingError::Misaligned);
+        }
+        match addr.checked_add(len) {
+            Some(end) if end <= VA_LIMIT => Ok(()),
+            _ => Err(PagingError::InvalidAddress),
+        }
+    }
+
+    /// Level 3 table covering `va`, creating missing tables if `create` is set
+    fn walk<F: FrameAllocator>(
+        &self,
+        frames: &mut F,
+        va: usize,
+        create: bool,
+    ) -> Result<Option<usize>, PagingError> {
+        let mut table = self.root;
+        for level in 0..LEVELS - 1 {
+            let entry = frames.table(table)[index(va, level)];
+            table = if entry & VALID != 0 {
+                (entry & ADDR_MASK) as usize
+            } else if create {
+                let next = Self::new_table(frames)?;
+                frames.table(table)[index(va, level)] = next as u64 | VALID | TABLE_OR_PAGE;
+                next
+            } else {
+                return Ok(None);
+            };
+        }
+        Ok(Some(table))
+    }
+
+    /// Rewrite the page descriptor of every page in the range, failing before
+    /// any change if one of them is not mapped
+    fn update<F: FrameAllocator>(
+        &mut self,
+        frames: &mut F,
+        va: usize,
+        len: usize,
+        rewrite: impl Fn(u64) -> u64,
+    ) -> Result<(), PagingError> {
+        Self::check_range(va, len)?;
+        for offset in (0..len).step_by(PAGE_SIZE) {
+            if self.translate(frames, va + offset).is_none() {
+                return Err(PagingError::NotMapped);
+            }
+        }
+
+        for offset in (0..len).step_by(PAGE_SIZE) {
+            let table = self.walk(frames, va + offset, false)?.ok_or(PagingError::NotMapped)?;
+            let entry = &mut frames.table(table)[index(va + offset, LEVELS - 1)];
+            *entry = rewrite(*entry);
+            invalidate(self.asid, va + offset);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    /// Frames held in host memory at made-up physical addresses
+    struct HostFrames {
+        frames: Vec<Box<[u64; ENTRIES]>>,
+        free: Vec<usize>,
+        limit: usize,
+    }
+
+    const HOST_BASE: usize = 0x4000_0000;
+
+    impl HostFrames {
+        fn new(limit: usize) -> Self {
+            Self { frames: Vec::new(), free: Vec::new(), limit }
+        }
+
+        fn live(&self) -> usize {
+            self.frames.len() - self.free.len()
+        }
+    }
+
+    impl FrameAllocator for HostFrames {
+        fn allocate_frame(&mut self) -> Option<usize> {
+            if let Some(frame) = self.free.pop() {
+                return Some(frame);
+            }
+            if self.frames.len() == self.limit {
+                return None;
+            }
+            self.frames.push(Box::new([0xDEAD; ENTRIES]));
+            Some(HOST_BASE + (self.frames.len() - 1) * PAGE_SIZE)
+        }
+
+        fn free_frame(&mut self, frame: usize) {
+            assert!(!self.free.contains(&frame), "double free of {frame:#x}");
+            self.free.push(frame);
+        }
+
+        fn table(&mut self, frame: usize) -> &mut [u64; ENTRIES] {
+            &mut self.frames[(frame - HOST_BASE) / PAGE_SIZE]
+        }
+    }
+
+    #[test]
+    fn test_map_translate_unmap() {
+        let mut frames = HostFrames::new(64);
+        let mut space = AddressSpace::new(&mut frames, 7).unwrap();
+        assert_eq!(space.ttbr0(), (7 << 48) | HOST_BASE as u64);
+
+        // Three pages straddling a 2MB boundary need two level 3 tables
+        let va = 0x40_0000_0000 + 0x20_0000 - PAGE_SIZE;
+        let user_rw = Access::READ_WRITE.union(Access::USER);
+        space.map(&mut frames, va, 0x8000_0000, 3 * PAGE_SIZE, user_rw).unwrap();
+        assert_eq!(frames.live(), 5);
+
+        assert_eq!(space.translate(&mut frames, va + 0x123), Some((0x8000_0123, user_rw)));
+        assert_eq!(
+            space.translate(&mut frames, va + 2 * PAGE_SIZE),
+            Some((0x8000_2000, user_rw))
+        );
+        assert_eq!(space.translate(&mut frames, va + 3 * PAGE_SIZE), None);
+
+        let table = space.walk(&mut frames, va, false).unwrap().unwrap();
+        let desc = frames.table(table)[index(va, 3)];
+        assert_eq!(desc & 0b11, 0b11);
+        assert_ne!(desc & AP_EL0, 0);
+        assert_ne!(desc & UXN, 0);
+
+        space.unmap(&mut frames, va, PAGE_SIZE).unwrap();
+        assert_eq!(space.translate(&mut frames, va), None);
+        assert_eq!(space.unmap(&mut frames, va, 2 * PAGE_SIZE), Err(PagingError::NotMapped));
+        assert!(space.translate(&mut frames, va + PAGE_SIZE).is_some());
+
+        space.destroy(&mut frames);
+        assert_eq!(frames.live(), 0);
+    }
+
+    #[test]
+    fn test_protect_and_conflicts() {
+        let mut frames = HostFrames::new(64);
+        let mut space = AddressSpace::new(&mut frames, 1).unwrap();
+        space.map(&mut frames, 0x1000, 0x9000, 2 * PAGE_SIZE, Access::READ_WRITE).unwrap();
+
+        // Same frames and permissions again is fine, anything else clashes
+        space.map(&mut frames, 0x1000, 0x9000, PAGE_SIZE, Access::READ_WRITE).unwrap();
+        assert_eq!(
+            space.map(&mut frames, 0x2000, 0x7000, PAGE_SIZE, Access::READ_WRITE),
+            Err(PagingError::AlreadyMapped)
+        );
+
+        space.protect(&mut frames, 0x2000, PAGE_SIZE, Access::READ).unwrap();
+        assert_eq!(space.translate(&mut frames, 0x2000), Some((0xA000, Access::READ)));
+        assert_eq!(space.translate(&mut frames, 0x1000), Some((0x9000, Access::READ_WRITE)));
+        let exec = Access::READ.union(Access::EXECUTE);
+        space.protect(&mut frames, 0x1000, PAGE_SIZE, exec).unwrap();
+        assert_eq!(space.translate(&mut frames, 0x1000), Some((0x9000, exec)));
+
+        assert_eq!(
+            space.protect(&mut frames, 0x1000, 3 * PAGE_SIZE, Access::READ),
+            Err(PagingError::NotMapped)
+        );
+        assert_eq!(space.map(&mut frames, 0x1800, 0x9000, PAGE_SIZE, Access::READ), Err(PagingError::Misaligned));
+        assert_eq!(space.map(&mut frames, 0x1000, 0x9000, PAGE_SIZE, Access::WRITE), Err(PagingError::InvalidAccess));
+        assert_eq!(
+            space.map(&mut frames, VA_LIMIT - PAGE_SIZE, 0x9000, 2 * PAGE_SIZE, Access::READ),
+            Err(PagingError::InvalidAddress)
+        );
+    }
+
+    #[test]
+    fn test_out_of_frames_maps_nothing() {
+        // Root, one level 1 and one level 2 table, but no room for level 3
+        let mut frames = HostFrames::new(3);
+        let mut space = AddressSpace::new(&mut frames, 1).unwrap();
+        assert_eq!(
+            space.map(&mut frames, 0x1000, 0x9000, PAGE_SIZE, Access::READ),
+            Err(PagingError::OutOfFrames)
+        );
+        assert_eq!(space.translate(&mut frames, 0x1000), None);
+
+        space.destroy(&mut frames);
+        assert_eq!(frames.live(), 0);
+    }
+}
@@ -0,0 +1,217 @@
+//! Per-owner memory quotas
+//! Enforces the `@memory(budget: ...)` an application declares
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use super::smme::AllocationError;
+
+/// Allocation owner (process / application id)
+pub type OwnerId = u32;
+
+/// Owner used by the kernel itself
+pub const KERNEL_OWNER: OwnerId = 0;
+/// Owners that can be tracked at once
+pub const MAX_OWNERS: usize = 32;
+/// Limit of an owner that never had a quota set
+pub const UNLIMITED: usize = usize::MAX;
+
+// Slot owner value for an unused entry
+const FREE_SLOT: u32 = u32::MAX;
+
+struct QuotaEntry {
+    owner: AtomicU32,
+    limit: AtomicUsize,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl QuotaEntry {
+    const fn new() -> Self {
+        Self {
+            owner: AtomicU32::new(FREE_SLOT),
+            limit: AtomicUsize::new(UNLIMITED),
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+}
+
+pub struct QuotaTable {
+    entries: [QuotaEntry; MAX_OWNERS],
+}
+
+impl QuotaTable {
+    pub const fn new() -> Self {
+        Self {
+            entries: [const { QuotaEntry::new() }; MAX_OWNERS],
+        }
+    }
+
+    fn find(&self, owner: OwnerId) -> Option<&QuotaEntry> {
+        if owner == FREE_SLOT {
+            return None;
+        }
+        self.entries
+            .iter()
+            .find(|e| e.owner.load(Ordering::Acquire) == owner)
+    }
+
+    /// Existing entry for `owner`, or a newly claimed one
+    fn claim(&self, owner: OwnerId) -> Result<&QuotaEntry, AllocationError> {
+        if owner == FREE_SLOT {
+            return Err(AllocationError::InvalidRequest);
+        }
+        if let Some(entry) = self.find(owner) {
+            return Ok(entry);
+        }
+
+        for entry in &self.entries {
+            match entry
+                .owner
+                .compare_exchange(FREE_SLOT, owner, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Ok(entry),
+                // Another core registered the same owner first
+                Err(current) if current == owner => return Ok(entry),
+                Err(_) => {}
+            }
+        }
+        Err(AllocationError::QuotaExceeded)
+    }
+
+    /// Cap what `owner` may hold from now on; `UNLIMITED` lifts the quota, and the
+    /// owner's slot is given back once it holds nothing
+    pub fn set_limit(&self, owner: OwnerId, limit: usize) -> Result<(), AllocationError> {
+        if limit == UNLIMITED {
+            if let Some(entry) = self.find(owner) {
+                entry.limit.store(UNLIMITED, Ordering::Release);
+                self.release_if_idle(entry, owner);
+            }
+            return Ok(());
+        }
+        self.claim(owner)?.limit.store(limit, Ordering::Release);
+        Ok(())
+    }
+
+    /// Charge `bytes` to `owner`, failing if it would exceed the quota.
+    /// Owners without a quota are not tracked, so they never use up a slot.
+    pub fn charge(&self, owner: OwnerId, bytes: usize) -> Result<(), AllocationError> {
+        let Some(entry) = self.find(owner) else {
+            return Ok(());
+        };
+        let limit = entry.limit.load(Ordering::Acquire);
+
+        let used = entry
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|&total| total <= limit)
+            })
+            .map_err(|_| AllocationError::QuotaExceeded)?;
+        entry.peak.fetch_max(used + bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn refund(&self, owner: OwnerId, bytes: usize) {
+        if let Some(entry) = self.find(owner) {
+            let _ = entry
+                .used
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                    Some(used.saturating_sub(bytes))
+                });
+            self.release_if_idle(entry, owner);
+        }
+    }
+
+    /// Free the slot of an owner with no quota left and nothing charged
+    fn release_if_idle(&self, entry: &QuotaEntry, owner: OwnerId) {
+        if entry.limit.load(Ordering::Acquire) != UNLIMITED || entry.used.load(Ordering::Acquire) != 0 {
+            return;
+        }
+        // Reset before the slot becomes claimable; the next owner starts from zero
+        entry.peak.store(0, Ordering::Relaxed);
+        let _ = entry
+            .owner
+            .compare_exchange(owner, FREE_SLOT, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    pub fn usage(&self, owner: OwnerId) -> Option<OwnerUsage> {
+        self.find(owner).map(|entry| OwnerUsage {
+            owner,
+            used: entry.used.load(Ordering::Relaxed),
+            peak: entry.peak.load(Ordering::Relaxed),
+            limit: entry.limit.load(Ordering::Relaxed),
+        })
+    }
+
+    pub fn snapshot(&self) -> [Option<OwnerUsage>; MAX_OWNERS] {
+        let mut owners = [None; MAX_OWNERS];
+        for (out, entry) in owners.iter_mut().zip(self.entries.iter()) {
+            let owner = entry.owner.load(Ordering::Acquire);
+            if owner != FREE_SLOT {
+                *out = self.usage(owner);
+            }
+        }
+        owners
+    }
+}
+
+/// Memory charged to one owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnerUsage {
+    pub owner: OwnerId,
+    pub used: usize,
+    pub peak: usize,
+    pub limit: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_within_limit() {
+        let quotas = QuotaTable::new();
+        quotas.set_limit(7, 1000).unwrap();
+
+        quotas.charge(7, 600).unwrap();
+        assert!(matches!(quotas.charge(7, 500), Err(AllocationError::QuotaExceeded)));
+
+        quotas.refund(7, 600);
+        quotas.charge(7, 1000).unwrap();
+        let usage = quotas.usage(7).unwrap();
+        assert_eq!((usage.used, usage.peak, usage.limit), (1000, 1000, 1000));
+    }
+
+    #[test]
+    fn test_untracked_owners_are_unlimited() {
+        let quotas = QuotaTable::new();
+        quotas.charge(KERNEL_OWNER, 1 << 40).unwrap();
+        quotas.charge(3, 1 << 40).unwrap();
+        assert!(quotas.usage(3).is_none());
+        assert!(quotas.snapshot().iter().all(|o| o.is_none()));
+    }
+
+    #[test]
+    fn test_table_full() {
+        let quotas = QuotaTable::new();
+        for owner in 1..=MAX_OWNERS as u32 {
+            quotas.set_limit(owner, 100).unwrap();
+            quotas.charge(owner, 1).unwrap();
+        }
+        let extra = MAX_OWNERS as u32 + 1;
+        assert!(matches!(quotas.set_limit(extra, 100), Err(AllocationError::QuotaExceeded)));
+        assert_eq!(quotas.snapshot().iter().flatten().count(), MAX_OWNERS);
+
+        // Owners without a quota still allocate
+        quotas.charge(KERNEL_OWNER, 1).unwrap();
+        quotas.charge(extra + 1, 1).unwrap();
+
+        // Lifting a quota frees the slot once the owner holds nothing
+        quotas.set_limit(1, UNLIMITED).unwrap();
+        assert!(quotas.set_limit(extra, 100).is_err());
+        quotas.refund(1, 1);
+        assert!(quotas.usage(1).is_none());
+        quotas.set_limit(extra, 100).unwrap();
+        assert_eq!(quotas.usage(extra).unwrap().peak, 0);
+    }
+}
@@ -0,0 +1,313 @@
+//! Redzones - canary bytes around every allocation (`smme-debug` builds only)
+//! Overruns are caught on free and by the periodic check in `kernel_tick`
+
+use core::fmt;
+use core::panic::Location;
+use core::ptr;
+
+use super::quota::OwnerId;
+use super::smme::{AllocationError, PAGE_SIZE};
+use super::spin::SpinLock;
+
+/// Canary bytes after every allocation; the front redzone is at least this big
+pub const REDZONE_SIZE: usize = 16;
+/// Allocations tracked before the tracker borrows pages from SMME
+pub const INLINE_TRACKED: usize = 256;
+
+const CANARY: u8 = 0xFD;
+
+/// A live allocation and where it came from
+#[derive(Debug, Clone, Copy)]
+pub struct TrackedAllocation {
+    pub addr: usize,
+    pub size: usize,
+    pub owner: OwnerId,
+    pub site: &'static Location<'static>,
+    // Front redzone length; keeps `addr` aligned
+    front: usize,
+}
+
+/// A damaged canary, reported with the allocation it belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct Corruption {
+    pub allocation: TrackedAllocation,
+    /// Address of the first damaged canary byte
+    pub at: usize,
+}
+
+impl Corruption {
+    pub fn is_underrun(&self) -> bool {
+        self.at < self.allocation.addr
+    }
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_underrun() { "underrun" } else { "overrun" };
+        write!(
+            f,
+            "SMME redzone {} at {:#x}: {} byte allocation at {:#x} by owner {}, allocated at {}",
+            kind,
+            self.at,
+            self.allocation.size,
+            self.allocation.addr,
+            self.allocation.owner,
+            self.allocation.site
+        )
+    }
+}
+
+/// Entries in each page borrowed once the inline table is full
+const PAGE_ENTRIES: usize =
+    (PAGE_SIZE - core::mem::size_of::<usize>()) / core::mem::size_of::<Option<TrackedAllocation>>();
+
+/// A page of extra entries, chained through `next`
+#[repr(C)]
+struct OverflowPage {
+    next: usize,
+    entries: [Option<TrackedAllocation>; PAGE_ENTRIES],
+}
+
+const _: () = assert!(core::mem::size_of::<OverflowPage>() <= PAGE_SIZE);
+
+struct Entries {
+    inline: [Option<TrackedAllocation>; INLINE_TRACKED],
+    // Address of the first overflow page, zero if none
+    overflow: usize,
+    // Damage found while freeing, kept for the next `check_all`
+    freed_damaged: Option<Corruption>,
+}
+
+impl Entries {
+    /// Every slot, inline ones first
+    fn slots(&mut self) -> impl Iterator<Item = &mut Option<TrackedAllocation>> {
+        let mut next = self.overflow;
+        let pages = core::iter::from_fn(move || {
+            // Overflow pages belong to the tracker for good once added
+            let page = unsafe { (next as *mut OverflowPage).as_mut()? };
+            next = page.next;
+            Some(page.entries.iter_mut())
+        });
+        self.inline.iter_mut().chain(pages.flatten())
+    }
+
+    /// # Safety
+    /// `page` must be a writable, page-aligned page nobody else uses
+    unsafe fn add_page(&mut self, page: usize) {
+        ptr::write(
+            page as *mut OverflowPage,
+            OverflowPage { next: self.overflow, entries: [None; PAGE_ENTRIES] },
+        );
+        self.overflow = page;
+    }
+}
+
+pub struct RedzoneTracker {
+    entries: SpinLock<Entries>,
+}
+
+impl RedzoneTracker {
+    pub const fn new() -> Self {
+        Self {
+            entries: SpinLock::new(Entries {
+                inline: [None; INLINE_TRACKED],
+                overflow: 0,
+                freed_damaged: None,
+            }),
+        }
+    }
+
+    /// Block size and front redzone needed to guard `size` bytes at `align`
+    pub fn padding(size: usize, align: usize) -> Result<(usize, usize), AllocationError> {
+        let front = REDZONE_SIZE.max(align);
+        let block = size
+            .checked_add(front)
+            .and_then(|n| n.checked_add(REDZONE_SIZE))
+            .ok_or(AllocationError::InvalidRequest)?;
+        Ok((block, front))
+    }
+
+    /// Fill the redzones of a fresh block and start tracking it; returns the user address.
+    /// Once every slot is taken, `grow` is asked for a page to hold more.
+    ///
+    /// # Safety
+    /// `block` must be writable for the padded size returned by `padding`, and pages
+    /// from `grow` must be writable and handed to the tracker for good
+    pub unsafe fn arm(
+        &self,
+        block: usize,
+        front: usize,
+        size: usize,
+        owner: OwnerId,
+        site: &'static Location<'static>,
+        grow: impl FnOnce() -> Option<usize>,
+    ) -> Result<usize, AllocationError> {
+        let allocation = TrackedAllocation {
+            addr: block + front,
+            size,
+            owner,
+            site,
+            front,
+        };
+
+        let mut entries = self.entries.lock();
+        if !entries.slots().any(|e| e.is_none()) {
+            entries.add_page(grow().ok_or(AllocationError::OutOfMemory)?);
+        }
+        let slot = entries
+            .slots()
+            .find(|e| e.is_none())
+            .ok_or(AllocationError::OutOfMemory)?;
+        *slot = Some(allocation);
+
+        ptr::write_bytes(block as *mut u8, CANARY, front);
+        ptr::write_bytes((allocation.addr + size) as *mut u8, CANARY, REDZONE_SIZE);
+        Ok(allocation.addr)
+    }
+
+    /// Check the canaries of the allocation at `addr` one last time and hand it to `release`.
+    /// Tracking stops only once `release` succeeded; damage is kept for `check_all` rather
+    /// than reported here, since this runs inside `dealloc`.
+    ///
+    /// # Safety
+    /// The allocation's redzones must still be mapped
+    pub unsafe fn disarm(
+        &self,
+        addr: usize,
+        size: usize,
+        release: impl FnOnce(&TrackedAllocation) -> Result<(), AllocationError>,
+    ) -> Result<(), AllocationError> {
+        let mut entries = self.entries.lock();
+        let (slot, allocation) = entries
+            .slots()
+            .find_map(|e| match *e {
+                Some(a) if a.addr == addr => Some((e, a)),
+                _ => None,
+            })
+            .ok_or(AllocationError::InvalidAddress)?;
+        if allocation.size != size {
+            return Err(AllocationError::InvalidRequest);
+        }
+
+        let corruption = Self::inspect(&allocation);
+        release(&allocation)?;
+        *slot = None;
+        if entries.freed_damaged.is_none() {
+            entries.freed_damaged = corruption;
+        }
+        Ok(())
+    }
+
+    /// First damaged redzone found while freeing since the last call,
+    /// otherwise the first among all live allocations
+    ///
+    /// # Safety
+    /// Every tracked allocation's redzones must still be mapped
+    pub unsafe fn check_all(&self) -> Option<Corruption> {
+        let mut entries = self.entries.lock();
+        match entries.freed_damaged.take() {
+            Some(corruption) => Some(corruption),
+            None => entries.slots().flatten().find_map(|a| Self::inspect(a)),
+        }
+    }
+
+    /// Start of the block holding `allocation`, for releasing it
+    pub fn block_start(allocation: &TrackedAllocation) -> usize {
+        allocation.addr - allocation.front
+    }
+
+    pub fn tracked(&self) -> usize {
+        self.entries.lock().slots().flatten().count()
+    }
+
+    unsafe fn inspect(allocation: &TrackedAllocation) -> Option<Corruption> {
+        let front = Self::block_start(allocation);
+        let back = allocation.addr + allocation.size;
+        Self::first_damaged(front, allocation.front)
+            .or_else(|| Self::first_damaged(back, REDZONE_SIZE))
+            .map(|at| Corruption { allocation: *allocation, at })
+    }
+
+    unsafe fn first_damaged(start: usize, len: usize) -> Option<usize> {
+        (start..start + len).find(|&addr| ptr::read_volatile(addr as *const u8) != CANARY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::smme::SymbianModernMemoryEngine;
+
+    #[test]
+    fn test_tracker_reports_culprit() {
+        let tracker = RedzoneTracker::new();
+        let mut block = [0u8; 128];
+        let (padded, front) = RedzoneTracker::padding(40, 32).unwrap();
+        assert_eq!((padded, front), (40 + 32 + REDZONE_SIZE, 32));
+
+        let site = Location::caller();
+        let base = block.as_mut_ptr();
+        let addr = unsafe { tracker.arm(base as usize, front, 40, 9, site, || None) }.unwrap();
+        assert_eq!(addr % 32, base as usize % 32);
+        assert!(unsafe { tracker.check_all() }.is_none());
+
+        // One byte past the end
+        unsafe { *base.add(front + 40) = 0 };
+        let corruption = unsafe { tracker.check_all() }.unwrap();
+        assert_eq!(corruption.at, addr + 40);
+        assert!(!corruption.is_underrun());
+        assert_eq!(corruption.allocation.owner, 9);
+        assert_eq!(corruption.allocation.site.line(), site.line());
+
+        // A failed release keeps the allocation tracked
+        let refused = unsafe { tracker.disarm(addr, 40, |_| Err(AllocationError::InvalidAddress)) };
+        assert!(refused.is_err());
+        assert_eq!(tracker.tracked(), 1);
+
+        let mut released = 0;
+        unsafe {
+            tracker.disarm(addr, 40, |a| {
+                released = RedzoneTracker::block_start(a);
+                Ok(())
+            })
+        }
+        .unwrap();
+        assert_eq!(released, base as usize);
+        assert_eq!(tracker.tracked(), 0);
+        // The damage is reported once, after the free
+        assert_eq!(unsafe { tracker.check_all() }.unwrap().at, addr + 40);
+        assert!(unsafe { tracker.check_all() }.is_none());
+    }
+
+
+    #[test]
+    fn test_engine_checks_redzones() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let cap = smme.allocate_for(4, 100).unwrap();
+        let addr = smme.access(4, &cap, 0, 100, crate::memory::capability::Rights::WRITE).unwrap();
+
+        unsafe { ptr::write_bytes(addr as *mut u8, 0xAA, 100) };
+        assert!(smme.check_redzones().is_none());
+
+        // Underrun by one byte
+        unsafe { *((addr - 1) as *mut u8) = 0 };
+        let corruption = smme.check_redzones().unwrap();
+        assert!(corruption.is_underrun());
+        assert_eq!(corruption.allocation.owner, 4);
+        assert!(corruption.allocation.site.file().ends_with("redzone.rs"));
+    }
+
+    #[test]
+    fn test_free_detects_overrun() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let cap = smme.allocate(64 * 1024).unwrap();
+        let addr = cap.addr();
+        unsafe { *((addr + 64 * 1024) as *mut u8) = 0 };
+
+        // The block is freed; the damage waits for the next check
+        smme.free(cap).unwrap();
+        let corruption = smme.check_redzones().unwrap();
+        assert_eq!(corruption.at, addr + 64 * 1024);
+        assert!(corruption.to_string().contains("redzone overrun"));
+    }
+}
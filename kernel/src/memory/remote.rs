@@ -0,0 +1,133 @@
+//! Layer 3 - allocations living on other devices of the mesh (HarmonyOS DNA)
+//! SMME keeps a handle per remote block; the bytes travel over the Quantum Bus
+
+use crate::bus::DeviceId;
+
+use super::smme::AllocationError;
+use super::spin::SpinLock;
+
+/// Remote allocations tracked at once
+pub const MAX_REMOTE: usize = 64;
+
+/// Handle to memory on another device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct RemoteHandle {
+    device: DeviceId,
+    generation: u32,
+    remote_addr: usize,
+    len: usize,
+    slot: usize,
+}
+
+impl RemoteHandle {
+    pub fn device(&self) -> DeviceId {
+        self.device
+    }
+
+    /// Address of the block in the peer's address space
+    pub fn remote_addr(&self) -> usize {
+        self.remote_addr
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RemoteEntry {
+    device: DeviceId,
+    remote_addr: usize,
+    len: usize,
+    generation: u32,
+    live: bool,
+}
+
+pub struct RemoteTable {
+    entries: SpinLock<[RemoteEntry; MAX_REMOTE]>,
+}
+
+impl RemoteTable {
+    pub const fn new() -> Self {
+        const EMPTY: RemoteEntry = RemoteEntry {
+            device: 0,
+            remote_addr: 0,
+            len: 0,
+            generation: 0,
+            live: false,
+        };
+        Self {
+            entries: SpinLock::new([EMPTY; MAX_REMOTE]),
+        }
+    }
+
+    pub fn insert(
+        &self,
+        device: DeviceId,
+        remote_addr: usize,
+        len: usize,
+    ) -> Result<RemoteHandle, AllocationError> {
+        let mut entries = self.entries.lock();
+        let slot = entries
+            .iter()
+            .position(|e| !e.live)
+            .ok_or(AllocationError::OutOfMemory)?;
+
+        let entry = &mut entries[slot];
+        entry.device = device;
+        entry.remote_addr = remote_addr;
+        entry.len = len;
+        entry.live = true;
+
+        Ok(RemoteHandle {
+            device,
+            generation: entry.generation,
+            remote_addr,
+            len,
+            slot,
+        })
+    }
+
+    /// Check that `handle` still names a live remote block
+    pub fn validate(&self, handle: &RemoteHandle) -> Result<(), AllocationError> {
+        Self::check(&self.entries.lock(), handle)
+    }
+
+    fn check(entries: &[RemoteEntry; MAX_REMOTE], handle: &RemoteHandle) -> Result<(), AllocationError> {
+        let entry = entries.get(handle.slot).ok_or(AllocationError::InvalidAddress)?;
+        if !entry.live || entry.generation != handle.generation {
+            return Err(AllocationError::UseAfterFree);
+        }
+        if (entry.device, entry.remote_addr, entry.len)
+            != (handle.device, handle.remote_addr, handle.len)
+        {
+            return Err(AllocationError::AccessDenied);
+        }
+        Ok(())
+    }
+
+    /// Forget the block; every copy of the handle goes stale
+    pub fn remove(&self, handle: &RemoteHandle) -> Result<(), AllocationError> {
+        let mut entries = self.entries.lock();
+        Self::check(&entries, handle)?;
+
+        let entry = &mut entries[handle.slot];
+        entry.live = false;
+        entry.generation = entry.generation.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Number of remote blocks and the bytes they hold
+    pub fn usage(&self) -> (usize, usize) {
+        self.entries
+            .lock()
+            .iter()
+            .filter(|e| e.live)
+            .fold((0, 0), |(count, bytes), e| (count + 1, bytes + e.len))
+    }
+}
@@ -0,0 +1,136 @@
+//! Predictive Reservoir - blocks pre-allocated ahead of demand
+//! The Oracle picks the size class; SMME fills the reservoir in `kernel_tick`
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use super::spin::SpinLock;
+
+/// Blocks kept ready for the predicted size class
+pub const RESERVOIR_SLOTS: usize = 4;
+
+struct ReservoirState {
+    // Request size the blocks were allocated for; zero when unset
+    target: usize,
+    blocks: [usize; RESERVOIR_SLOTS],
+    count: usize,
+}
+
+pub struct Reservoir {
+    state: SpinLock<ReservoirState>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl Reservoir {
+    pub const fn new() -> Self {
+        Self {
+            state: SpinLock::new(ReservoirState {
+                target: 0,
+                blocks: [0; RESERVOIR_SLOTS],
+                count: 0,
+            }),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Hand out a block if `matches` accepts the current target size
+    pub fn take(&self, matches: impl Fn(usize) -> bool) -> Option<usize> {
+        let mut state = self.state.lock();
+        if state.target == 0 {
+            return None;
+        }
+
+        if state.count > 0 && matches(state.target) {
+            state.count -= 1;
+            let block = state.blocks[state.count];
+            drop(state);
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Some(block)
+        } else {
+            drop(state);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+
+    /// Switch to a new target, returning the stale blocks and the size they were allocated for
+    pub fn retarget(&self, target: usize) -> (usize, [usize; RESERVOIR_SLOTS], usize) {
+        let mut state = self.state.lock();
+        let old = (state.target, state.blocks, state.count);
+        state.target = target;
+        state.count = 0;
+        old
+    }
+
+    pub fn target(&self) -> usize {
+        self.state.lock().target
+    }
+
+    /// Number of empty slots
+    pub fn vacancies(&self) -> usize {
+        RESERVOIR_SLOTS - self.state.lock().count
+    }
+
+    /// Store a block allocated for `target`; handed back if the target moved on or the reservoir is full
+    pub fn put(&self, target: usize, block: usize) -> Result<(), usize> {
+        let mut state = self.state.lock();
+        if state.target != target || state.count == RESERVOIR_SLOTS {
+            return Err(block);
+        }
+        let count = state.count;
+        state.blocks[count] = block;
+        state.count += 1;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ReservoirStats {
+        ReservoirStats {
+            target: self.target(),
+            ready: RESERVOIR_SLOTS - self.vacancies(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReservoirStats {
+    pub target: usize,
+    pub ready: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl ReservoirStats {
+    /// Percentage of allocations served from the reservoir
+    pub fn hit_rate(&self) -> usize {
+        (self.hits * 100)
+            .checked_div(self.hits + self.misses)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_and_retarget() {
+        let reservoir = Reservoir::new();
+        assert_eq!(reservoir.take(|_| true), None);
+        assert_eq!(reservoir.stats().misses, 0);
+
+        reservoir.retarget(4096);
+        reservoir.put(4096, 0x1000).unwrap();
+        assert_eq!(reservoir.put(8192, 0x2000), Err(0x2000));
+
+        assert_eq!(reservoir.take(|target| target == 8192), None);
+        assert_eq!(reservoir.take(|target| target == 4096), Some(0x1000));
+
+        let (old, blocks, count) = reservoir.retarget(8192);
+        assert_eq!((old, count), (4096, 0));
+        assert_eq!(blocks[0], 0x1000);
+        assert_eq!(reservoir.stats().hit_rate(), 50);
+    }
+}
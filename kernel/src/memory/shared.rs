@@ -0,0 +1,412 @@
+//! Shared Regions - bulk data passed between active objects without copying
+//! Read-only sharers get a private copy the first time they write (copy-on-write)
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::capability::{keyed_mix, Rights};
+use super::quota::OwnerId;
+use super::smme::AllocationError;
+use super::spin::SpinLock;
+
+/// Shared regions alive at once
+pub const MAX_SHARED_REGIONS: usize = 32;
+/// Holders across all regions
+pub const MAX_SHARED_MAPPINGS: usize = 128;
+
+// Tokens carry the mapping slot in their low byte and the tag above it
+const TOKEN_SLOT_BITS: u32 = 8;
+const _: () = assert!(MAX_SHARED_MAPPINGS <= 1 << TOKEN_SLOT_BITS);
+
+/// One holder's access to a shared region.
+/// Travels between active objects as `token()` in `Message::data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedHandle {
+    mapping: u32,
+    generation: u32,
+    holder: OwnerId,
+    // Keyed over the fields above, so a token names its holder
+    tag: u64,
+}
+
+impl SharedHandle {
+    fn seal(mapping: u32, generation: u32, holder: OwnerId, key: u64) -> Self {
+        let tag = keyed_mix(key, &[mapping as u64, generation as u64, holder as u64]) >> TOKEN_SLOT_BITS;
+        Self { mapping, generation, holder, tag }
+    }
+
+    pub fn holder(&self) -> OwnerId {
+        self.holder
+    }
+
+    /// Message payload identifying this handle; `SharedTable::redeem` turns it back
+    pub fn token(&self) -> u64 {
+        (self.tag << TOKEN_SLOT_BITS) | self.mapping as u64
+    }
+}
+
+/// What a holder currently sees through its handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedView {
+    pub addr: usize,
+    pub size: usize,
+    pub align: usize,
+    pub rights: Rights,
+    /// Holders of the underlying region, this one included
+    pub refs: u32,
+    region: usize,
+}
+
+/// A block whose last holder let go; SMME frees it on the owner's behalf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Orphan {
+    pub addr: usize,
+    pub size: usize,
+    pub align: usize,
+    pub owner: OwnerId,
+}
+
+#[derive(Clone, Copy)]
+struct Region {
+    addr: usize,
+    size: usize,
+    align: usize,
+    // Charged for the block
+    owner: OwnerId,
+    refs: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Mapping {
+    region: usize,
+    holder: OwnerId,
+    rights: Rights,
+    generation: u32,
+    live: bool,
+}
+
+struct SharedState {
+    regions: [Option<Region>; MAX_SHARED_REGIONS],
+    mappings: [Mapping; MAX_SHARED_MAPPINGS],
+}
+
+impl SharedState {
+    fn mapping(&self, handle: &SharedHandle) -> Result<&Mapping, AllocationError> {
+        let mapping = self
+            .mappings
+            .get(handle.mapping as usize)
+            .ok_or(AllocationError::InvalidAddress)?;
+        if !mapping.live || mapping.generation != handle.generation {
+            return Err(AllocationError::UseAfterFree);
+        }
+        if mapping.holder != handle.holder {
+            return Err(AllocationError::AccessDenied);
+        }
+        Ok(mapping)
+    }
+
+    fn add_mapping(
+        &mut self,
+        region: usize,
+        holder: OwnerId,
+        rights: Rights,
+        key: u64,
+    ) -> Result<SharedHandle, AllocationError> {
+        let slot = self
+            .mappings
+            .iter()
+            .position(|m| !m.live)
+            .ok_or(AllocationError::OutOfMemory)?;
+
+        let mapping = &mut self.mappings[slot];
+        *mapping = Mapping {
+            region,
+            holder,
+            rights,
+            generation: mapping.generation,
+            live: true,
+        };
+        Ok(SharedHandle::seal(slot as u32, mapping.generation, holder, key))
+    }
+
+    /// Drop one reference to `region`, handing back its block if nobody holds it any more
+    fn unref(&mut self, region: usize) -> Option<Orphan> {
+        let entry = self.regions[region].as_mut()?;
+        entry.refs -= 1;
+        if entry.refs > 0 {
+            return None;
+        }
+        let orphan = Orphan {
+            addr: entry.addr,
+            size: entry.size,
+            align: entry.align,
+            owner: entry.owner,
+        };
+        self.regions[region] = None;
+        Some(orphan)
+    }
+}
+
+pub struct SharedTable {
+    state: SpinLock<SharedState>,
+    key: AtomicU64,
+}
+
+impl SharedTable {
+    pub const fn new() -> Self {
+        const UNMAPPED: Mapping = Mapping {
+            region: 0,
+            holder: 0,
+            rights: Rights::NONE,
+            generation: 0,
+            live: false,
+        };
+        Self {
+            state: SpinLock::new(SharedState {
+                regions: [None; MAX_SHARED_REGIONS],
+                mappings: [UNMAPPED; MAX_SHARED_MAPPINGS],
+            }),
+            key: AtomicU64::new(0x2545_F491_4F6C_DD1D),
+        }
+    }
+
+    /// Seed the token key at boot; tokens issued under the old key stop working
+    pub fn set_key(&self, key: u64) {
+        self.key.store(key, Ordering::Release);
+    }
+
+    /// Rebuild a handle received in a message. The token names the holder it was
+    /// granted to, so whoever presents it acts as that holder and nobody else.
+    pub fn redeem(&self, token: u64) -> Result<SharedHandle, AllocationError> {
+        let slot = (token & ((1 << TOKEN_SLOT_BITS) - 1)) as u32;
+        let state = self.state.lock();
+        let mapping = state
+            .mappings
+            .get(slot as usize)
+            .ok_or(AllocationError::InvalidAddress)?;
+        let handle = SharedHandle::seal(slot, mapping.generation, mapping.holder, self.key.load(Ordering::Acquire));
+        if handle.token() != token {
+            // Forged, or the mapping was released and its slot moved on
+            return Err(if mapping.live { AllocationError::AccessDenied } else { AllocationError::UseAfterFree });
+        }
+        state.mapping(&handle)?;
+        Ok(handle)
+    }
+
+    /// Start sharing a block; `owner` gets the first, read-write handle
+    pub fn create(
+        &self,
+        addr: usize,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<SharedHandle, AllocationError> {
+        let mut state = self.state.lock();
+        let region = state
+            .regions
+            .iter()
+            .position(|r| r.is_none())
+            .ok_or(AllocationError::OutOfMemory)?;
+
+        let handle = state.add_mapping(region, owner, Rights::READ_WRITE, self.key.load(Ordering::Acquire))?;
+        state.regions[region] = Some(Region { addr, size, align, owner, refs: 1 });
+        Ok(handle)
+    }
+
+    pub fn view(&self, handle: &SharedHandle) -> Result<SharedView, AllocationError> {
+        let state = self.state.lock();
+        let mapping = state.mapping(handle)?;
+        let region = state.regions[mapping.region].ok_or(AllocationError::UseAfterFree)?;
+        Ok(SharedView {
+            addr: region.addr,
+            size: region.size,
+            align: region.align,
+            rights: mapping.rights,
+            refs: region.refs,
+            region: mapping.region,
+        })
+    }
+
+    /// Give `to` its own handle to the region; nobody can grant more than they hold
+    pub fn grant(&self, handle: &SharedHandle, to: OwnerId, rights: Rights) -> Result<SharedHandle, AllocationError> {
+        if !rights.contains(Rights::READ) {
+            return Err(AllocationError::InvalidRequest);
+        }
+
+        let mut state = self.state.lock();
+        let mapping = *state.mapping(handle)?;
+        if !mapping.rights.contains(rights) {
+            return Err(AllocationError::AccessDenied);
+        }
+
+        let granted = state.add_mapping(mapping.region, to, rights, self.key.load(Ordering::Acquire))?;
+        if let Some(region) = state.regions[mapping.region].as_mut() {
+            region.refs += 1;
+        }
+        Ok(granted)
+    }
+
+    /// Make a read-only mapping writable because it is the region's only holder.
+    /// The holder takes over the block, so `recharge` moves its charge from the
+    /// previous owner first; if that fails the mapping stays read-only.
+    pub fn claim_sole(
+        &self,
+        handle: &SharedHandle,
+        view: &SharedView,
+        recharge: impl FnOnce(OwnerId) -> Result<(), AllocationError>,
+    ) -> Result<bool, AllocationError> {
+        let mut state = self.state.lock();
+        let mapping = *state.mapping(handle)?;
+        let region = match state.regions[view.region].as_mut() {
+            Some(region) if mapping.region == view.region && region.refs == 1 => region,
+            _ => return Ok(false),
+        };
+        if region.owner != handle.holder {
+            recharge(region.owner)?;
+            region.owner = handle.holder;
+        }
+        state.mappings[handle.mapping as usize].rights = Rights::READ_WRITE;
+        Ok(true)
+    }
+
+    /// Move a read-only mapping onto its private copy at `addr`, charged to the holder
+    pub fn detach(
+        &self,
+        handle: &SharedHandle,
+        view: &SharedView,
+        addr: usize,
+    ) -> Result<Option<Orphan>, AllocationError> {
+        let mut state = self.state.lock();
+        let mapping = *state.mapping(handle)?;
+        // Another write from the same holder may have got there first
+        let shared = state.regions[mapping.region]
+            .filter(|_| mapping.region == view.region)
+            .ok_or(AllocationError::InvalidRequest)?;
+
+        let region = state
+            .regions
+            .iter()
+            .position(|r| r.is_none())
+            .ok_or(AllocationError::OutOfMemory)?;
+        state.regions[region] = Some(Region {
+            addr,
+            size: shared.size,
+            align: shared.align,
+            owner: handle.holder,
+            refs: 1,
+        });
+
+        let entry = &mut state.mappings[handle.mapping as usize];
+        entry.region = region;
+        entry.rights = Rights::READ_WRITE;
+        Ok(state.unref(view.region))
+    }
+
+    /// Drop a handle; returns the block to free if it was the last one
+    pub fn release(&self, handle: &SharedHandle) -> Result<Option<Orphan>, AllocationError> {
+        let mut state = self.state.lock();
+        let region = state.mapping(handle)?.region;
+
+        let entry = &mut state.mappings[handle.mapping as usize];
+        entry.live = false;
+        entry.generation = entry.generation.wrapping_add(1);
+        Ok(state.unref(region))
+    }
+
+    /// Live regions and handles
+    pub fn usage(&self) -> (usize, usize) {
+        let state = self.state.lock();
+        (
+            state.regions.iter().flatten().count(),
+            state.mappings.iter().filter(|m| m.live).count(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::smme::SymbianModernMemoryEngine;
+
+    #[test]
+    fn test_grants_and_refcounts() {
+        let table = SharedTable::new();
+        let owner = table.create(0x1000, 4096, 1, 1).unwrap();
+        let reader = table.grant(&owner, 2, Rights::READ).unwrap();
+
+        // Tokens name the object they were granted to and can't be retargeted
+        let token = reader.token();
+        let received = table.redeem(token).unwrap();
+        assert_eq!((received, received.holder()), (reader, 2));
+        let forged = SharedHandle::seal(reader.mapping, reader.generation, 3, 0);
+        assert!(matches!(table.redeem(forged.token()), Err(AllocationError::AccessDenied)));
+        assert!(matches!(table.redeem(token ^ 1 << 40), Err(AllocationError::AccessDenied)));
+        assert!(matches!(
+            table.grant(&reader, 3, Rights::READ_WRITE),
+            Err(AllocationError::AccessDenied)
+        ));
+
+        assert_eq!(table.view(&owner).unwrap().refs, 2);
+        assert_eq!(table.release(&owner).unwrap(), None);
+        assert!(matches!(table.view(&owner), Err(AllocationError::UseAfterFree)));
+        assert_eq!(
+            table.release(&reader).unwrap(),
+            Some(Orphan { addr: 0x1000, size: 4096, align: 1, owner: 1 })
+        );
+        assert_eq!(table.usage(), (0, 0));
+        assert!(matches!(table.redeem(token), Err(AllocationError::UseAfterFree)));
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let writer = smme.create_shared(1, 8192).unwrap();
+        smme.write_shared(&writer, 0, b"frame-1").unwrap();
+
+        // The grant reaches the reader as a message payload
+        let token = smme.grant_shared(&writer, 2, Rights::READ).unwrap().token();
+        let reader = smme.shared_from_token(token).unwrap();
+        let other = smme.grant_shared(&writer, 3, Rights::READ).unwrap();
+        smme.set_quota(2, 1 << 20).unwrap();
+
+        // The read-only sharer's write lands in its own copy
+        smme.write_shared(&reader, 0, b"mine").unwrap();
+        let mut buf = [0u8; 7];
+        smme.read_shared(&reader, 0, &mut buf).unwrap();
+        assert_eq!(&buf, b"minee-1");
+        smme.read_shared(&other, 0, &mut buf).unwrap();
+        assert_eq!(&buf, b"frame-1");
+
+        // Writers still share with the remaining readers
+        smme.write_shared(&writer, 0, b"frame-2").unwrap();
+        smme.read_shared(&other, 0, &mut buf).unwrap();
+        assert_eq!(&buf, b"frame-2");
+        assert!(smme.owner_usage(2).unwrap().used >= 8192);
+    }
+
+    #[test]
+    fn test_last_holder_frees_region() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let base = smme.stats().total_reserved;
+        smme.set_quota(1, 1 << 20).unwrap();
+
+        let owner = smme.create_shared(1, 4096).unwrap();
+        let reader = smme.grant_shared(&owner, 2, Rights::READ).unwrap();
+        smme.release_shared(owner).unwrap();
+        assert!(smme.stats().total_reserved > base);
+
+        // Sole holder writes in place once it takes over the charge
+        smme.set_quota(2, 1024).unwrap();
+        assert!(matches!(smme.write_shared(&reader, 0, b"x"), Err(AllocationError::QuotaExceeded)));
+        let charged = smme.owner_usage(1).unwrap().used;
+        assert!(charged >= 4096);
+
+        smme.set_quota(2, 1 << 20).unwrap();
+        smme.write_shared(&reader, 0, b"x").unwrap();
+        assert_eq!(smme.owner_usage(2).unwrap().used, charged);
+        assert_eq!(smme.owner_usage(1).unwrap().used, 0);
+
+        smme.release_shared(reader).unwrap();
+        assert_eq!(smme.stats().total_reserved, base);
+        assert!(matches!(smme.release_shared(reader), Err(AllocationError::UseAfterFree)));
+    }
+}
@@ -0,0 +1,373 @@
+//! Slab Layer - fixed size classes on top of the L0 fast pool
+//! Small kernel objects are carved out of 4KB slab pages
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use super::smme::{AllocationError, MemoryPool};
+
+/// Object sizes served by the slab layer, 16..2048 bytes
+pub const SLAB_CLASSES: [usize; 8] = [16, 32, 64, 128, 256, 512, 1024, 2048];
+
+const SLAB_PAGE_SIZE: usize = 4096;
+const MAX_SLABS_PER_CLASS: usize = 16;
+const SLOT_WORDS: usize = SLAB_PAGE_SIZE / 16 / 64;
+
+/// One 4KB page split into equal slots
+struct SlabPage {
+    // Zero while the slot in the class table is unused
+    base: AtomicUsize,
+    // One bit per slot, set while the slot is free
+    free: [AtomicU64; SLOT_WORDS],
+}
+
+impl SlabPage {
+    const fn new() -> Self {
+        Self {
+            base: AtomicUsize::new(0),
+            free: [const { AtomicU64::new(0) }; SLOT_WORDS],
+        }
+    }
+
+    /// Take any free slot, returning its index
+    fn take_slot(&self) -> Option<usize> {
+        for (w, word) in self.free.iter().enumerate() {
+            let taken = word.fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+                if bits == 0 { None } else { Some(bits & (bits - 1)) }
+            });
+            if let Ok(bits) = taken {
+                return Some(w * 64 + bits.trailing_zeros() as usize);
+            }
+        }
+        None
+    }
+
+    fn has_free_slot(&self) -> bool {
+        self.free.iter().any(|w| w.load(Ordering::Acquire) != 0)
+    }
+}
+
+/// All slab pages of a single size class
+struct SlabClass {
+    object_size: usize,
+    pages: [SlabPage; MAX_SLABS_PER_CLASS],
+    // Bit i set while page i may still have free slots
+    partial: AtomicU64,
+    in_use: AtomicUsize,
+    page_count: AtomicUsize,
+    // Index + 1 of the empty page kept for the next allocation, zero if none
+    cached: AtomicUsize,
+}
+
+impl SlabClass {
+    const fn new(object_size: usize) -> Self {
+        Self {
+            object_size,
+            pages: [const { SlabPage::new() }; MAX_SLABS_PER_CLASS],
+            partial: AtomicU64::new(0),
+            in_use: AtomicUsize::new(0),
+            page_count: AtomicUsize::new(0),
+            cached: AtomicUsize::new(0),
+        }
+    }
+
+    fn slots_per_page(&self) -> usize {
+        SLAB_PAGE_SIZE / self.object_size
+    }
+
+    /// Free-bit mask of a fully free page for word `w`
+    fn full_mask(&self, w: usize) -> u64 {
+        let slots = self.slots_per_page();
+        let start = w * 64;
+        if slots >= start + 64 {
+            u64::MAX
+        } else if slots > start {
+            (1u64 << (slots - start)) - 1
+        } else {
+            0
+        }
+    }
+
+    fn allocate(&self, backing: &MemoryPool) -> Result<usize, AllocationError> {
+        loop {
+            let partial = self.partial.load(Ordering::Acquire);
+            if partial == 0 {
+                self.grow(backing)?;
+                continue;
+            }
+
+            let idx = partial.trailing_zeros() as usize;
+            let page = &self.pages[idx];
+            if let Some(slot) = page.take_slot() {
+                // A cached page stops being empty; the next empty page may take its place
+                let _ = self.cached.compare_exchange(idx + 1, 0, Ordering::AcqRel, Ordering::Acquire);
+                self.in_use.fetch_add(1, Ordering::Relaxed);
+                return Ok(page.base.load(Ordering::Acquire) + slot * self.object_size);
+            }
+
+            // Page is full; drop it from the partial set, then recheck for a racing free
+            self.partial.fetch_and(!(1 << idx), Ordering::AcqRel);
+            if page.has_free_slot() {
+                self.partial.fetch_or(1 << idx, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Back a new page with memory from the pool
+    fn grow(&self, backing: &MemoryPool) -> Result<(), AllocationError> {
+        let base = backing.reserve_aligned(SLAB_PAGE_SIZE, SLAB_PAGE_SIZE)?;
+        backing.commit(base, SLAB_PAGE_SIZE)?;
+
+        for (idx, page) in self.pages.iter().enumerate() {
+            if page
+                .base
+                .compare_exchange(0, base, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                for (w, word) in page.free.iter().enumerate() {
+                    word.store(self.full_mask(w), Ordering::Release);
+                }
+                self.page_count.fetch_add(1, Ordering::Relaxed);
+                self.partial.fetch_or(1 << idx, Ordering::AcqRel);
+                return Ok(());
+            }
+        }
+
+        // Class table is full
+        backing.release(base, SLAB_PAGE_SIZE)?;
+        Err(AllocationError::OutOfMemory)
+    }
+
+    fn free(&self, addr: usize, backing: &MemoryPool) -> Result<(), AllocationError> {
+        let (idx, page) = self
+            .pages
+            .iter()
+            .enumerate()
+            .find(|(_, p)| {
+                let base = p.base.load(Ordering::Acquire);
+                base != 0 && addr >= base && addr < base + SLAB_PAGE_SIZE
+            })
+            .ok_or(AllocationError::InvalidAddress)?;
+
+        let offset = addr - page.base.load(Ordering::Acquire);
+        if !offset.is_multiple_of(self.object_size) {
+            return Err(AllocationError::InvalidAddress);
+        }
+
+        let slot = offset / self.object_size;
+        let bit = 1u64 << (slot % 64);
+        let prev = page.free[slot / 64].fetch_or(bit, Ordering::AcqRel);
+        if prev & bit != 0 {
+            // Double free
+            return Err(AllocationError::InvalidAddress);
+        }
+
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+        self.partial.fetch_or(1 << idx, Ordering::AcqRel);
+        self.try_retire(idx, backing, true);
+        Ok(())
+    }
+
+    /// Release the cached empty page, if it is still empty
+    fn trim(&self, backing: &MemoryPool) {
+        let cached = self.cached.swap(0, Ordering::AcqRel);
+        if cached != 0 {
+            self.try_retire(cached - 1, backing, false);
+        }
+    }
+
+    /// Hand a completely free page back to the pool, unless it can become
+    /// the class's cached page (`cache`)
+    fn try_retire(&self, idx: usize, backing: &MemoryPool, cache: bool) {
+        let page = &self.pages[idx];
+
+        // Claim every slot; failure means the page is still in use
+        for w in 0..SLOT_WORDS {
+            let full = self.full_mask(w);
+            if page.free[w]
+                .compare_exchange(full, 0, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                for restored in 0..w {
+                    page.free[restored].store(self.full_mask(restored), Ordering::Release);
+                }
+                return;
+            }
+        }
+
+        // One empty page stays, so a class hovering at a page boundary doesn't churn the pool
+        let marker = idx + 1;
+        if cache
+            && (self.cached.load(Ordering::Acquire) == marker
+                || self
+                    .cached
+                    .compare_exchange(0, marker, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok())
+        {
+            for w in 0..SLOT_WORDS {
+                page.free[w].store(self.full_mask(w), Ordering::Release);
+            }
+            return;
+        }
+
+        self.partial.fetch_and(!(1 << idx), Ordering::AcqRel);
+        let base = page.base.swap(0, Ordering::AcqRel);
+        self.page_count.fetch_sub(1, Ordering::Relaxed);
+        let _ = backing.release(base, SLAB_PAGE_SIZE);
+    }
+
+    fn stats(&self) -> SlabClassStats {
+        let pages = self.page_count.load(Ordering::Relaxed);
+        SlabClassStats {
+            object_size: self.object_size,
+            pages,
+            in_use: self.in_use.load(Ordering::Relaxed),
+            capacity: pages * self.slots_per_page(),
+        }
+    }
+}
+
+/// Size-class allocator layered over a backing pool
+pub struct SlabAllocator {
+    classes: [SlabClass; SLAB_CLASSES.len()],
+}
+
+impl SlabAllocator {
+    pub const fn new() -> Self {
+        Self {
+            classes: [
+                SlabClass::new(SLAB_CLASSES[0]),
+                SlabClass::new(SLAB_CLASSES[1]),
+                SlabClass::new(SLAB_CLASSES[2]),
+                SlabClass::new(SLAB_CLASSES[3]),
+                SlabClass::new(SLAB_CLASSES[4]),
+                SlabClass::new(SLAB_CLASSES[5]),
+                SlabClass::new(SLAB_CLASSES[6]),
+                SlabClass::new(SLAB_CLASSES[7]),
+            ],
+        }
+    }
+
+    /// Size class serving a request, if it is small enough for a slab
+    pub fn class_for(size: usize, align: usize) -> Option<usize> {
+        let needed = size.max(align);
+        SLAB_CLASSES.iter().position(|&class| needed <= class)
+    }
+
+    pub fn object_size(class: usize) -> usize {
+        SLAB_CLASSES[class]
+    }
+
+    pub fn allocate(&self, class: usize, backing: &MemoryPool) -> Result<usize, AllocationError> {
+        self.classes[class].allocate(backing)
+    }
+
+    pub fn free(&self, class: usize, addr: usize, backing: &MemoryPool) -> Result<(), AllocationError> {
+        self.classes[class].free(addr, backing)
+    }
+
+    /// Give every class's cached empty page back to the pool
+    pub fn trim(&self, backing: &MemoryPool) {
+        for class in &self.classes {
+            class.trim(backing);
+        }
+    }
+
+    pub fn stats(&self) -> [SlabClassStats; SLAB_CLASSES.len()] {
+        let mut stats = [SlabClassStats::empty(); SLAB_CLASSES.len()];
+        for (out, class) in stats.iter_mut().zip(self.classes.iter()) {
+            *out = class.stats();
+        }
+        stats
+    }
+}
+
+/// Occupancy of one size class
+#[derive(Debug, Clone, Copy)]
+pub struct SlabClassStats {
+    pub object_size: usize,
+    pub pages: usize,
+    pub in_use: usize,
+    pub capacity: usize,
+}
+
+impl SlabClassStats {
+    pub const fn empty() -> Self {
+        Self { object_size: 0, pages: 0, in_use: 0, capacity: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_selection() {
+        assert_eq!(SlabAllocator::class_for(1, 1), Some(0));
+        assert_eq!(SlabAllocator::class_for(17, 8), Some(1));
+        assert_eq!(SlabAllocator::class_for(8, 256), Some(4));
+        assert_eq!(SlabAllocator::class_for(2049, 1), None);
+    }
+
+    #[test]
+    fn test_slab_reuse_and_retire() {
+        let pool = MemoryPool::new(0x1000_0000, 64 * 1024);
+        let slab = SlabAllocator::new();
+
+        let a = slab.allocate(3, &pool).unwrap();
+        let b = slab.allocate(3, &pool).unwrap();
+        assert_eq!(b, a + 128);
+        assert_eq!(pool.usage().0, SLAB_PAGE_SIZE);
+
+        slab.free(3, a, &pool).unwrap();
+        assert_eq!(slab.allocate(3, &pool).unwrap(), a);
+
+        slab.free(3, a, &pool).unwrap();
+        slab.free(3, b, &pool).unwrap();
+        assert!(slab.free(3, b, &pool).is_err());
+
+        // The empty page stays cached for the next allocation
+        assert_eq!(pool.usage().0, SLAB_PAGE_SIZE);
+        assert_eq!(slab.stats()[3].pages, 1);
+        assert_eq!(slab.allocate(3, &pool).unwrap(), a);
+        slab.free(3, a, &pool).unwrap();
+
+        slab.trim(&pool);
+        assert_eq!(pool.usage().0, 0);
+        assert_eq!(slab.stats()[3].pages, 0);
+    }
+
+    #[test]
+    fn test_slab_caches_one_empty_page() {
+        let pool = MemoryPool::new(0x1000_0000, 64 * 1024);
+        let slab = SlabAllocator::new();
+
+        // One 2KB object on each of three pages
+        let objects: [usize; 6] = core::array::from_fn(|_| slab.allocate(7, &pool).unwrap());
+        for &addr in objects.iter().step_by(2) {
+            slab.free(7, addr, &pool).unwrap();
+        }
+        for &addr in objects.iter().skip(1).step_by(2) {
+            slab.free(7, addr, &pool).unwrap();
+        }
+
+        // Only the first page to empty is kept
+        assert_eq!(slab.stats()[7].pages, 1);
+        assert_eq!(pool.usage().0, SLAB_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_slab_grows_across_pages() {
+        let pool = MemoryPool::new(0x1000_0000, 64 * 1024);
+        let slab = SlabAllocator::new();
+
+        // Two 2KB objects per page
+        for _ in 0..5 {
+            slab.allocate(7, &pool).unwrap();
+        }
+
+        let stats = slab.stats()[7];
+        assert_eq!(stats.pages, 3);
+        assert_eq!(stats.in_use, 5);
+        assert_eq!(stats.capacity, 6);
+    }
+}
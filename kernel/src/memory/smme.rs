@@ -4,15 +4,59 @@
 #![no_std]
 
 use core::alloc::{GlobalAlloc, Layout};
-use core::ptr::NonNull;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+use crate::bus::{BusError, DeviceMesh, MemoryTransport};
+
+use super::buddy::BuddyAllocator;
+use super::capability::{Capability, CapabilityTable, Rights};
+use super::cleanup::{LowMemoryHandlers, PurgeableRegistry};
+use super::layout::{
+    LayoutError, MemoryRegion, PoolLayout, L0_POOL_SIZE, L1_POOL_SIZE, L2_POOL_MAX, L2_POOL_MIN,
+};
+#[cfg(test)]
+use super::layout::POOL_ALIGN;
+#[cfg(feature = "smme-debug")]
+use super::redzone::{Corruption, RedzoneTracker};
+use super::paging::{Access, AddressSpace, FrameAllocator, PagingError, ENTRIES};
+use super::quota::{OwnerId, OwnerUsage, QuotaTable, KERNEL_OWNER, MAX_OWNERS};
+use super::remote::{RemoteHandle, RemoteTable};
+use super::reservoir::{Reservoir, ReservoirStats};
+use super::shared::{SharedHandle, SharedTable, SharedView};
+use super::slab::{SlabAllocator, SlabClassStats, SLAB_CLASSES};
+use super::spin::SpinLock;
+use super::stats::{MemoryStatsSnapshot, PoolCounters, PoolStats, STATS_VERSION};
+
+/// Number of bitmap words tracking granule occupancy in each pool
+const POOL_BITMAP_WORDS: usize = 16;
+/// Maximum number of granules a pool can track
+const POOL_MAX_GRANULES: usize = POOL_BITMAP_WORDS * 64;
+/// Committed percentage of any pool that raises a pressure warning
+pub const WARNING_PRESSURE_PCT: usize = 80;
+/// Committed percentage of any pool that makes pressure critical
+pub const CRITICAL_PRESSURE_PCT: usize = 95;
+/// Number of allocation events kept for the Oracle
+pub const HISTORY_LEN: usize = 16;
+/// Commit granularity; also the smallest pool granule, so pages are never shared
+pub const PAGE_SIZE: usize = 4096;
+/// Smallest allocation unit handed out by a pool
+const MIN_GRANULE: usize = PAGE_SIZE;
+/// Number of bitmap words tracking committed pages, enough for the largest pool
+const COMMIT_BITMAP_WORDS: usize = L2_POOL_MAX / PAGE_SIZE / 64;
 
 /// Memory Pool with Symbian-style two-phase allocation
 pub struct MemoryPool {
     base: usize,
     size: usize,
+    granule: usize,
     reserved: AtomicUsize,
     committed: AtomicUsize,
+    peak_committed: AtomicUsize,
+    // One bit per granule, set while the granule is reserved
+    occupancy: [AtomicU64; POOL_BITMAP_WORDS],
+    // One bit per page, set while the page is backed by physical memory
+    commit_map: [AtomicU64; COMMIT_BITMAP_WORDS],
 }
 
 impl MemoryPool {
@@ -20,34 +64,153 @@ impl MemoryPool {
         Self {
             base,
             size,
+            granule: Self::granule_for(size),
             reserved: AtomicUsize::new(0),
             committed: AtomicUsize::new(0),
+            peak_committed: AtomicUsize::new(0),
+            occupancy: [const { AtomicU64::new(0) }; POOL_BITMAP_WORDS],
+            commit_map: [const { AtomicU64::new(0) }; COMMIT_BITMAP_WORDS],
         }
     }
 
+    /// Smallest power-of-two granule that lets the bitmap cover `size`
+    const fn granule_for(size: usize) -> usize {
+        let mut granule = MIN_GRANULE;
+        while granule * POOL_MAX_GRANULES < size {
+            granule *= 2;
+        }
+        granule
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.base + self.size
+    }
+
+    /// Size rounded up to whole granules (at least one); saturates instead of wrapping
+    pub fn rounded(&self, size: usize) -> usize {
+        size.div_ceil(self.granule).max(1).saturating_mul(self.granule)
+    }
+
     /// Phase 1: Reserve virtual address space (Symbian DNA)
     pub fn reserve(&self, size: usize) -> Result<usize, AllocationError> {
-        let old = self.reserved.fetch_add(size, Ordering::AcqRel);
-        if old + size > self.size {
-            self.reserved.fetch_sub(size, Ordering::Release);
-            return Err(AllocationError::OutOfMemory);
+        self.reserve_aligned(size, 1)
+    }
+
+    /// Reserve a range whose start address is a multiple of `align`
+    pub fn reserve_aligned(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        let size = self.rounded(size);
+        self.charge(size)?;
+
+        // Enough bytes are free, but they may be fragmented
+        match self.claim_run(size / self.granule, align) {
+            Some(first) => Ok(self.base + first * self.granule),
+            None => {
+                self.reserved.fetch_sub(size, Ordering::Release);
+                Err(AllocationError::OutOfMemory)
+            }
         }
-        Ok(self.base + old)
     }
 
-    /// Phase 2: Commit physical memory
+    /// Account for `size` more reserved bytes. The CAS loop never lets `reserved`
+    /// pass the pool size, even transiently, so failures can't starve other cores.
+    fn charge(&self, size: usize) -> Result<(), AllocationError> {
+        self.reserved
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+                reserved.checked_add(size).filter(|&total| total <= self.size)
+            })
+            .map(|_| ())
+            .map_err(|_| AllocationError::OutOfMemory)
+    }
+
+    /// Bytes from the pool base to the end of `addr..addr + size`, if that can be computed
+    fn span_end(&self, addr: usize, size: usize) -> Option<usize> {
+        addr.checked_sub(self.base)?.checked_add(size)
+    }
+
+    /// Phase 2: Commit physical memory for every page touching the range
     pub fn commit(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
-        if addr < self.base || addr + size > self.base + self.size {
+        self.check_reserved(addr, size)?;
+
+        let first = (addr - self.base) / PAGE_SIZE;
+        let last = (addr - self.base + size).div_ceil(PAGE_SIZE);
+        let changed = self.update_pages(first, last, true);
+        let committed = self.committed.fetch_add(changed * PAGE_SIZE, Ordering::AcqRel);
+        self.peak_committed
+            .fetch_max(committed + changed * PAGE_SIZE, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Release the physical pages lying entirely inside the range, keeping the reservation
+    pub fn decommit(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
+        self.check_reserved(addr, size)?;
+
+        let first = (addr - self.base).div_ceil(PAGE_SIZE);
+        let last = (addr - self.base + size) / PAGE_SIZE;
+        if first < last {
+            let changed = self.update_pages(first, last, false);
+            self.committed.fetch_sub(changed * PAGE_SIZE, Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    /// Commit and decommit only make sense inside a live reservation
+    fn check_reserved(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
+        let end = self.span_end(addr, size).ok_or(AllocationError::InvalidAddress)?;
+        if !self.contains(addr) || end > self.page_count() * PAGE_SIZE {
             return Err(AllocationError::InvalidAddress);
         }
-        
-        let offset = addr - self.base;
-        let old_committed = self.committed.load(Ordering::Acquire);
-        
-        if offset + size > old_committed {
-            self.committed.store(offset + size, Ordering::Release);
+
+        let first = (addr - self.base) / self.granule;
+        let last = (addr - self.base + size).div_ceil(self.granule).max(first + 1);
+        if (first..last).any(|g| !self.is_claimed(g)) {
+            return Err(AllocationError::InvalidAddress);
         }
-        
+        Ok(())
+    }
+
+    /// Reserve the exact range starting at granule `first`, as chosen by a placement policy
+    pub fn reserve_at(&self, first: usize, size: usize) -> Result<usize, AllocationError> {
+        let size = self.rounded(size);
+        let end = first
+            .checked_mul(self.granule)
+            .and_then(|offset| offset.checked_add(size));
+        if end.is_none_or(|end| end > self.size) {
+            return Err(AllocationError::InvalidAddress);
+        }
+
+        self.charge(size)?;
+        if !self.update_run(first, size / self.granule, true) {
+            self.reserved.fetch_sub(size, Ordering::Release);
+            return Err(AllocationError::InvalidAddress);
+        }
+        Ok(self.base + first * self.granule)
+    }
+
+    /// Return a reserved range to the pool so later reservations can reuse it
+    pub fn release(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
+        let size = self.rounded(size);
+        if !self.contains(addr)
+            || !(addr - self.base).is_multiple_of(self.granule)
+            || self.span_end(addr, size).is_none_or(|end| end > self.size)
+        {
+            return Err(AllocationError::InvalidAddress);
+        }
+
+        let first = (addr - self.base) / self.granule;
+        let count = size / self.granule;
+        if (first..first + count).any(|g| !self.is_claimed(g)) {
+            // Double free or size mismatch
+            return Err(AllocationError::InvalidAddress);
+        }
+
+        // Granules never share pages, so every page of the range can go
+        let pages_first = (addr - self.base) / PAGE_SIZE;
+        let pages_last = (pages_first + size / PAGE_SIZE).min(self.page_count());
+        let changed = self.update_pages(pages_first, pages_last, false);
+        self.committed.fetch_sub(changed * PAGE_SIZE, Ordering::AcqRel);
+
+        self.update_run(first, count, false);
+        self.reserved.fetch_sub(size, Ordering::AcqRel);
         Ok(())
     }
 
@@ -55,157 +218,2210 @@ impl MemoryPool {
         (self.reserved.load(Ordering::Relaxed), 
          self.committed.load(Ordering::Relaxed))
     }
+
+    pub fn granule(&self) -> usize {
+        self.granule
+    }
+
+    /// Highest committed byte count since boot
+    pub fn peak_committed(&self) -> usize {
+        self.peak_committed.load(Ordering::Relaxed)
+    }
+
+    pub fn granule_count(&self) -> usize {
+        (self.size / self.granule).min(POOL_MAX_GRANULES)
+    }
+
+    /// Longest run of free granules, in granules
+    pub fn largest_free_run(&self) -> usize {
+        let mut largest = 0;
+        let mut run = 0;
+        for g in 0..self.granule_count() {
+            if self.is_claimed(g) {
+                run = 0;
+            } else {
+                run += 1;
+                largest = largest.max(run);
+            }
+        }
+        largest
+    }
+
+    fn page_count(&self) -> usize {
+        (self.size / PAGE_SIZE).min(COMMIT_BITMAP_WORDS * 64)
+    }
+
+    pub fn is_committed(&self, addr: usize) -> bool {
+        if !self.contains(addr) || (addr - self.base) / PAGE_SIZE >= self.page_count() {
+            return false;
+        }
+        let page = (addr - self.base) / PAGE_SIZE;
+        self.commit_map[page / 64].load(Ordering::Acquire) & (1 << (page % 64)) != 0
+    }
+
+    /// Set or clear commit bits for pages `first..last`, returning how many changed state
+    fn update_pages(&self, first: usize, last: usize, commit: bool) -> usize {
+        let mut changed = 0;
+        let mut page = first;
+
+        while page < last {
+            let bits = (last - page).min(64 - page % 64);
+            let mask = if bits == 64 { u64::MAX } else { ((1u64 << bits) - 1) << (page % 64) };
+
+            let flipped = if commit {
+                mask & !self.commit_map[page / 64].fetch_or(mask, Ordering::AcqRel)
+            } else {
+                mask & self.commit_map[page / 64].fetch_and(!mask, Ordering::AcqRel)
+            };
+            changed += flipped.count_ones() as usize;
+
+            page += bits;
+        }
+
+        changed
+    }
+
+    fn is_claimed(&self, granule: usize) -> bool {
+        let word = self.occupancy[granule / 64].load(Ordering::Acquire);
+        word & (1 << (granule % 64)) != 0
+    }
+
+    /// First-fit search for `count` free granules, claimed atomically
+    fn claim_run(&self, count: usize, align: usize) -> Option<usize> {
+        let total = self.granule_count();
+        let mut start = 0;
+
+        while start + count <= total {
+            if !(self.base + start * self.granule).is_multiple_of(align) {
+                start += 1;
+                continue;
+            }
+
+            match (start..start + count).rev().find(|&g| self.is_claimed(g)) {
+                Some(busy) => start = busy + 1,
+                None => {
+                    if self.update_run(start, count, true) {
+                        return Some(start);
+                    }
+                    // Lost a race with another core; rescan from the same spot
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Set or clear the bits of a granule run, one word at a time.
+    /// When setting, any conflicting bit rolls back the words already claimed.
+    fn update_run(&self, first: usize, count: usize, claim: bool) -> bool {
+        let end = first + count;
+        let mut granule = first;
+
+        while granule < end {
+            let word = granule / 64;
+            let bits = (end - granule).min(64 - granule % 64);
+            let mask = if bits == 64 { u64::MAX } else { ((1u64 << bits) - 1) << (granule % 64) };
+
+            if claim {
+                let claimed = self.occupancy[word].fetch_update(
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    |cur| if cur & mask == 0 { Some(cur | mask) } else { None },
+                );
+                if claimed.is_err() {
+                    if granule > first {
+                        self.update_run(first, granule - first, false);
+                    }
+                    return false;
+                }
+            } else {
+                self.occupancy[word].fetch_and(!mask, Ordering::AcqRel);
+            }
+
+            granule += bits;
+        }
+
+        true
+    }
 }
 
 /// Main SMME Allocator with 4-layer architecture
 pub struct SymbianModernMemoryEngine {
     // Layer 1: Core pools
     l0_pool: MemoryPool,  // 64KB fast pool
+    l0_slab: SlabAllocator, // Size classes carved from L0
     l1_pool: MemoryPool,  // 2MB general pool
     l2_pool: MemoryPool,  // 16MB large pool
+    l2_buddy: BuddyAllocator,
+    l2_buddy_enabled: AtomicBool,
     
     // Layer 2: Predictive state
-    allocation_history: [usize; 16],
+    allocation_history: [AtomicU64; HISTORY_LEN],
     history_index: AtomicUsize,
+    observer: AtomicPtr<()>,
+    reservoir: Reservoir,
+    purgeable: PurgeableRegistry,
+    low_memory: LowMemoryHandlers,
+    last_pressure: AtomicU8,
+    // Fallback across pools
+    spill_policy: AtomicU8,
+    spill_handler: AtomicPtr<()>,
+    counters: [PoolCounters; 3],
+    spills: AtomicUsize,
+    quotas: QuotaTable,
+    capabilities: CapabilityTable,
+    shared: SharedTable,
+    #[cfg(feature = "smme-debug")]
+    redzones: RedzoneTracker,
     
-    // Layer 3: Distributed (placeholder for v0.4)
-    distributed_enabled: bool,
+    // Layer 3: Distributed memory on mesh peers
+    distributed_enabled: AtomicBool,
+    transport: SpinLock<Option<&'static dyn MemoryTransport>>,
+    remote: RemoteTable,
 }
 
 impl SymbianModernMemoryEngine {
-    pub const fn new(total_ram: usize) -> Self {
-        // Simplified for no_std - in real impl, use proper memory regions
+    /// Default layout for boards without a memory map; L2 shrinks to fit small RAM,
+    /// but never below `L2_POOL_MIN`
+    pub const fn new(total_ram: usize) -> Result<Self, LayoutError> {
+        let l2_size = total_ram.saturating_sub(L0_POOL_SIZE + L1_POOL_SIZE);
+        let l2_size = if l2_size < 16 * 1024 * 1024 { l2_size } else { 16 * 1024 * 1024 };
+        if l2_size < L2_POOL_MIN {
+            return Err(LayoutError::InsufficientMemory);
+        }
+
+        Ok(Self::with_layout(PoolLayout {
+            l0: MemoryRegion::new(0x1000_0000, L0_POOL_SIZE),
+            l1: MemoryRegion::new(0x1001_0000, L1_POOL_SIZE),
+            l2: MemoryRegion::new(0x1021_0000, l2_size),
+        }))
+    }
+
+    /// Build the engine from the usable RAM regions reported at boot
+    pub fn from_memory_map(total_ram: usize, regions: &[MemoryRegion]) -> Result<Self, LayoutError> {
+        let layout = PoolLayout::from_memory_map(total_ram, regions)?;
+        Ok(Self::with_layout(layout))
+    }
+
+    /// Default pool sizes over leaked host memory, for tests that write to their allocations
+    #[cfg(test)]
+    pub fn host_backed() -> Self {
+        let total_ram = L0_POOL_SIZE + L1_POOL_SIZE + 16 * 1024 * 1024;
+        // Slack for aligning the first pool
+        let ram = std::vec![0u8; total_ram + POOL_ALIGN].leak();
+        let region = MemoryRegion::new(ram.as_ptr() as usize, ram.len());
+        Self::from_memory_map(total_ram, &[region]).unwrap()
+    }
+
+    /// Build the engine over a layout `new` or `PoolLayout::from_memory_map` has checked
+    const fn with_layout(layout: PoolLayout) -> Self {
         Self {
-            l0_pool: MemoryPool::new(0x1000_0000, 64 * 1024),
-            l1_pool: MemoryPool::new(0x1001_0000, 2 * 1024 * 1024),
-            l2_pool: MemoryPool::new(0x1021_0000, 16 * 1024 * 1024),
-            allocation_history: [0; 16],
+            l0_pool: MemoryPool::new(layout.l0.base, layout.l0.size),
+            l0_slab: SlabAllocator::new(),
+            l1_pool: MemoryPool::new(layout.l1.base, layout.l1.size),
+            l2_pool: MemoryPool::new(layout.l2.base, layout.l2.size),
+            l2_buddy: BuddyAllocator::new(),
+            l2_buddy_enabled: AtomicBool::new(false),
+            allocation_history: [const { AtomicU64::new(0) }; HISTORY_LEN],
             history_index: AtomicUsize::new(0),
-            distributed_enabled: false,
+            observer: AtomicPtr::new(ptr::null_mut()),
+            reservoir: Reservoir::new(),
+            purgeable: PurgeableRegistry::new(),
+            low_memory: LowMemoryHandlers::new(),
+            last_pressure: AtomicU8::new(MemoryPressure::Normal as u8),
+            spill_policy: AtomicU8::new(SpillPolicy::Never as u8),
+            spill_handler: AtomicPtr::new(ptr::null_mut()),
+            counters: [const { PoolCounters::new() }; 3],
+            spills: AtomicUsize::new(0),
+            quotas: QuotaTable::new(),
+            capabilities: CapabilityTable::new(),
+            shared: SharedTable::new(),
+            #[cfg(feature = "smme-debug")]
+            redzones: RedzoneTracker::new(),
+            distributed_enabled: AtomicBool::new(false),
+            transport: SpinLock::new(None),
+            remote: RemoteTable::new(),
         }
     }
 
     /// Smart allocation with pool selection
-    pub fn allocate(&self, size: usize) -> Result<usize, AllocationError> {
-        // Select pool based on size
-        let pool = if size <= 64 * 1024 {
+    #[cfg_attr(feature = "smme-debug", track_caller)]
+    pub fn allocate(&self, size: usize) -> Result<Capability, AllocationError> {
+        self.allocate_for(KERNEL_OWNER, size)
+    }
+
+    /// Allocate on behalf of `owner`, charging its memory quota
+    #[cfg_attr(feature = "smme-debug", track_caller)]
+    pub fn allocate_for(&self, owner: OwnerId, size: usize) -> Result<Capability, AllocationError> {
+        self.allocate_aligned_for(owner, size, 1)
+    }
+
+    /// Allocate `size` bytes starting at a multiple of `align` (DMA buffers, page-table frames).
+    /// L2 allocations are always page aligned.
+    #[cfg_attr(feature = "smme-debug", track_caller)]
+    pub fn allocate_aligned(&self, size: usize, align: usize) -> Result<Capability, AllocationError> {
+        self.allocate_aligned_for(KERNEL_OWNER, size, align)
+    }
+
+    #[cfg_attr(feature = "smme-debug", track_caller)]
+    pub fn allocate_aligned_for(
+        &self,
+        owner: OwnerId,
+        size: usize,
+        align: usize,
+    ) -> Result<Capability, AllocationError> {
+        let align = self.validate_request(size, align)?;
+        let addr = self.allocate_in_pool(size, align, owner)?;
+        self.capabilities
+            .issue(addr, size, align, owner, Rights::READ_WRITE)
+            .inspect_err(|_| {
+                let _ = self.free_in_pool(addr, size, align, owner);
+            })
+    }
+
+    /// Reject malformed requests; returns the alignment the block will actually get
+    fn validate_request(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        if size == 0 || !align.is_power_of_two() {
+            return Err(AllocationError::InvalidRequest);
+        }
+        if size.checked_next_multiple_of(align.max(PAGE_SIZE)).is_none() {
+            return Err(AllocationError::InvalidRequest);
+        }
+        // Nothing larger than the biggest pool can ever fit
+        if size > self.l2_pool.size.max(L1_POOL_SIZE) {
+            return Err(AllocationError::OutOfMemory);
+        }
+
+        if ptr::eq(self.pool_for_size(size), &self.l2_pool) {
+            Ok(align.max(PAGE_SIZE))
+        } else {
+            Ok(align)
+        }
+    }
+
+    /// Resolve `offset..offset + len` of an allocation, checking the handle and `rights`
+    pub fn access(
+        &self,
+        accessor: OwnerId,
+        cap: &Capability,
+        offset: usize,
+        len: usize,
+        rights: Rights,
+    ) -> Result<usize, AllocationError> {
+        self.capabilities.validate(cap, accessor, rights)?;
+        let end = offset.checked_add(len).ok_or(AllocationError::InvalidRequest)?;
+        if end > cap.len() {
+            return Err(AllocationError::InvalidAddress);
+        }
+        Ok(cap.addr() + offset)
+    }
+
+    /// Derive a handle to the same allocation with fewer rights
+    pub fn restrict(&self, cap: &Capability, rights: Rights) -> Result<Capability, AllocationError> {
+        self.capabilities.restrict(cap, rights)
+    }
+
+    /// Seed the capability tag and shared token keys at boot, before the first allocation
+    pub fn set_tag_key(&self, key: u64) {
+        self.capabilities.set_key(key);
+        self.shared.set_key(key.rotate_left(32));
+    }
+
+    /// Cap the bytes `owner` may hold, e.g. from its `@memory(budget: ...)` annotation.
+    /// Owners are only tracked while they have a quota, so set it before they allocate.
+    pub fn set_quota(&self, owner: OwnerId, budget: usize) -> Result<(), AllocationError> {
+        self.quotas.set_limit(owner, budget)
+    }
+
+    pub fn owner_usage(&self, owner: OwnerId) -> Option<OwnerUsage> {
+        self.quotas.usage(owner)
+    }
+
+    /// Choose the placement policy for L2; only allowed while L2 is empty
+    pub fn select_l2_allocator(&self, kind: L2Allocator) -> Result<(), AllocationError> {
+        if self.l2_pool.usage().0 != 0 {
+            return Err(AllocationError::InvalidRequest);
+        }
+
+        if kind == L2Allocator::Buddy {
+            self.l2_buddy.reset(self.l2_pool.granule_count());
+        }
+        self.l2_buddy_enabled
+            .store(kind == L2Allocator::Buddy, Ordering::Release);
+        Ok(())
+    }
+
+    fn uses_buddy(&self, pool: &MemoryPool) -> bool {
+        ptr::eq(pool, &self.l2_pool) && self.l2_buddy_enabled.load(Ordering::Acquire)
+    }
+
+    /// Buddy order covering both the size and the alignment of a request
+    fn buddy_order(&self, size: usize, align: usize) -> usize {
+        let granule = self.l2_pool.granule();
+        BuddyAllocator::order_for(size.div_ceil(granule))
+            .max(BuddyAllocator::order_for(align.div_ceil(granule)))
+    }
+
+    fn allocate_buddy(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        let order = self.buddy_order(size, align);
+        let block = self.l2_pool.granule() << order;
+        let first = self
+            .l2_buddy
+            .allocate(order)
+            .ok_or(AllocationError::OutOfMemory)?;
+
+        let addr = match self.l2_pool.reserve_at(first, block) {
+            Ok(addr) if addr.is_multiple_of(align) => addr,
+            Ok(addr) => {
+                let _ = self.l2_pool.release(addr, block);
+                self.l2_buddy.free(first, order);
+                return Err(AllocationError::OutOfMemory);
+            }
+            Err(err) => {
+                self.l2_buddy.free(first, order);
+                return Err(err);
+            }
+        };
+        self.l2_pool.commit(addr, size)?;
+        Ok(addr)
+    }
+
+    fn pool_for_size(&self, size: usize) -> &MemoryPool {
+        if size <= L0_POOL_SIZE {
             &self.l0_pool
-        } else if size <= 2 * 1024 * 1024 {
+        } else if size <= L1_POOL_SIZE {
             &self.l1_pool
         } else {
             &self.l2_pool
+        }
+    }
+
+    #[cfg(not(feature = "smme-debug"))]
+    fn allocate_in_pool(
+        &self,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<usize, AllocationError> {
+        self.allocate_charged(size, align, owner)
+    }
+
+    /// Debug builds pad every allocation with redzones and remember its call site
+    #[cfg(feature = "smme-debug")]
+    #[track_caller]
+    fn allocate_in_pool(
+        &self,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<usize, AllocationError> {
+        let site = core::panic::Location::caller();
+        let (padded, front) = RedzoneTracker::padding(size, align)?;
+        let block = self.allocate_charged(padded, align, owner)?;
+
+        // The pools are RAM owned by SMME and the block was just placed; tracker pages
+        // bypass the history and quotas like reservoir blocks
+        let grow = || self.allocate_block(PAGE_SIZE, PAGE_SIZE).ok();
+        unsafe { self.redzones.arm(block, front, size, owner, site, grow) }.inspect_err(|_| {
+            let _ = self.free_charged(block, padded, align, owner);
+        })
+    }
+
+    /// What `allocate_in_pool` charges the owner for `size` bytes
+    #[cfg(not(feature = "smme-debug"))]
+    fn charged_size(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        Ok(self.block_size(size, align))
+    }
+
+    #[cfg(feature = "smme-debug")]
+    fn charged_size(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        let (padded, _) = RedzoneTracker::padding(size, align)?;
+        Ok(self.block_size(padded, align))
+    }
+
+    /// Charge the owner, place the block and record the allocation event
+    fn allocate_charged(
+        &self,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<usize, AllocationError> {
+        // Owners pay for the whole block they occupy
+        let charge = self.block_size(size, align);
+        self.quotas.charge(owner, charge)?;
+
+        let placed = match self.take_from_reservoir(size, align) {
+            Some(addr) => Ok(addr),
+            None => self.allocate_block(size, align),
+        };
+        let addr = match placed {
+            Ok(addr) => addr,
+            Err(err) => {
+                self.quotas.refund(owner, charge);
+                return Err(err);
+            }
         };
 
-        // Two-phase allocation
-        let addr = pool.reserve(size)?;
-        pool.commit(addr, size)?;
+        // Update history for prediction, naming the pool that really served it
+        let pool = self.pool_for_addr(addr).unwrap_or(self.pool_for_size(size));
+        let event = AllocationEvent { size, pool: self.pool_id(pool) };
+        self.counters[event.pool as usize].record_allocation(size);
+        let idx = self.history_index.fetch_add(1, Ordering::Relaxed) % HISTORY_LEN;
+        self.allocation_history[idx].store(event.pack(), Ordering::Release);
+        self.notify_observer(event);
+
+        Ok(addr)
+    }
+
+    /// Place a block without touching the reservoir or the history
+    fn allocate_block(&self, size: usize, align: usize) -> Result<usize, AllocationError> {
+        let home = self.pool_for_size(size);
+        let policy = self.spill_policy();
+
+        let mut pool = home;
+        loop {
+            match self.allocate_block_in(pool, size, align) {
+                Err(AllocationError::OutOfMemory) => {
+                    self.counters[self.pool_id(pool) as usize].record_failure();
+                }
+                Ok(addr) if !ptr::eq(pool, home) => {
+                    self.spills.fetch_add(1, Ordering::Relaxed);
+                    if policy == SpillPolicy::Warn {
+                        self.notify_spill(SpillEvent {
+                            size,
+                            from: self.pool_id(home),
+                            to: self.pool_id(pool),
+                        });
+                    }
+                    return Ok(addr);
+                }
+                result => return result,
+            }
+
+            pool = match self.next_pool(pool) {
+                Some(next) if policy != SpillPolicy::Never => next,
+                _ => return Err(AllocationError::OutOfMemory),
+            };
+        }
+    }
+
+    /// Pool a request spills into when `pool` is exhausted
+    fn next_pool(&self, pool: &MemoryPool) -> Option<&MemoryPool> {
+        match self.pool_id(pool) {
+            PoolId::L0 => Some(&self.l1_pool),
+            PoolId::L1 => Some(&self.l2_pool),
+            PoolId::L2 => None,
+        }
+    }
 
-        // Update history for prediction
-        let idx = self.history_index.fetch_add(1, Ordering::Relaxed) % 16;
-        // Note: In real impl, this would be atomic or protected
-        // self.allocation_history[idx] = size;
+    fn allocate_block_in(
+        &self,
+        pool: &MemoryPool,
+        size: usize,
+        align: usize,
+    ) -> Result<usize, AllocationError> {
+        let slab_class = SlabAllocator::class_for(size, align).filter(|_| ptr::eq(pool, &self.l0_pool));
+        let addr = match slab_class {
+            // Small objects come from the L0 slab layer
+            Some(class) => self.l0_slab.allocate(class, &self.l0_pool)?,
+            None if self.uses_buddy(pool) => self.allocate_buddy(size, align)?,
+            None => {
+                // Two-phase allocation
+                let addr = pool.reserve_aligned(size, align)?;
+                pool.commit(addr, size)?;
+                addr
+            }
+        };
 
         Ok(addr)
     }
 
-    /// Predictive cleanup (Oracle Engine integration point)
-    pub fn predictive_cleanup(&self) -> usize {
-        let (reserved, committed) = self.l1_pool.usage();
-        let utilization = (committed * 100) / self.l1_pool.size;
-        
-        if utilization > 80 {
-            // Trigger cleanup - in v0.5 this will use ML
-            self.emergency_cleanup()
-        } else {
-            0
+    /// Reservoir blocks fit any request that would get the same block from the same pool
+    fn same_block(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        ptr::eq(self.pool_for_size(a.0), self.pool_for_size(b.0))
+            && self.block_size(a.0, a.1) == self.block_size(b.0, b.1)
+    }
+
+    fn take_from_reservoir(&self, size: usize, align: usize) -> Option<usize> {
+        // Reservoir blocks are only guaranteed page alignment
+        if align > PAGE_SIZE {
+            return None;
         }
+        self.reservoir
+            .take(|target| self.same_block((target, 1), (size, align)))
     }
 
-    /// Emergency cleanup (Symbian DNA)
-    pub fn emergency_cleanup(&self) -> usize {
-        // In full impl: Scan and reclaim unused allocations
-        // For now: Return amount that could be freed
-        let (reserved, committed) = self.l1_pool.usage();
-        reserved.saturating_sub(committed)
+    /// Top up the reservoir for the Oracle's predicted request size.
+    /// Returns the number of blocks added.
+    pub fn refill_reservoir(&self, predicted: usize) -> usize {
+        if predicted == 0 {
+            return 0;
+        }
+
+        let current = self.reservoir.target();
+        if current == 0 || !self.same_block((current, 1), (predicted, 1)) {
+            // Prediction moved to another size class; drop the stale blocks
+            let (old, blocks, count) = self.reservoir.retarget(predicted);
+            for &block in &blocks[..count] {
+                let _ = self.release_block(block, old, 1);
+            }
+        }
+
+        let target = self.reservoir.target();
+        let mut added = 0;
+        for _ in 0..self.reservoir.vacancies() {
+            let block = match self.allocate_block(target, 1) {
+                Ok(block) => block,
+                Err(_) => break,
+            };
+            if let Err(block) = self.reservoir.put(target, block) {
+                let _ = self.release_block(block, target, 1);
+                break;
+            }
+            added += 1;
+        }
+        added
     }
 
-    pub fn stats(&self) -> MemoryStats {
-        let (l0_res, l0_com) = self.l0_pool.usage();
-        let (l1_res, l1_com) = self.l1_pool.usage();
-        let (l2_res, l2_com) = self.l2_pool.usage();
-        
-        MemoryStats {
-            total_reserved: l0_res + l1_res + l2_res,
-            total_committed: l0_com + l1_com + l2_com,
-            l0_usage: l0_com,
-            l1_usage: l1_com,
-            l2_usage: l2_com,
+    fn pool_id(&self, pool: &MemoryPool) -> PoolId {
+        if ptr::eq(pool, &self.l0_pool) {
+            PoolId::L0
+        } else if ptr::eq(pool, &self.l1_pool) {
+            PoolId::L1
+        } else {
+            PoolId::L2
         }
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub struct MemoryStats {
-    pub total_reserved: usize,
-    pub total_committed: usize,
-    pub l0_usage: usize,
-    pub l1_usage: usize,
-    pub l2_usage: usize,
-}
+    /// Choose what happens when a request's own pool is exhausted
+    pub fn set_spill_policy(&self, policy: SpillPolicy) {
+        self.spill_policy.store(policy as u8, Ordering::Release);
+    }
 
-#[derive(Debug)]
-pub enum AllocationError {
-    OutOfMemory,
-    InvalidAddress,
-    InvalidRequest,
-}
+    pub fn spill_policy(&self) -> SpillPolicy {
+        SpillPolicy::from_u8(self.spill_policy.load(Ordering::Acquire))
+    }
 
-// Tests
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Register the hook called for every spill under `SpillPolicy::Warn`.
+    /// It runs inside the allocation path, so it must not allocate.
+    pub fn set_spill_handler(&self, handler: fn(SpillEvent)) {
+        self.spill_handler.store(handler as *mut (), Ordering::Release);
+    }
 
-    #[test]
-    fn test_two_phase_allocation() {
-        let smme = SymbianModernMemoryEngine::new(1 << 30);
-        
-        // Allocate 1MB
-        let addr = smme.allocate(1024 * 1024).unwrap();
-        assert!(addr > 0);
-        
-        // Check stats
-        let stats = smme.stats();
-        assert!(stats.total_committed > 0);
+    fn notify_spill(&self, event: SpillEvent) {
+        let handler = self.spill_handler.load(Ordering::Acquire);
+        if !handler.is_null() {
+            // Only ever set from a `fn(SpillEvent)` in `set_spill_handler`
+            let handler: fn(SpillEvent) = unsafe { core::mem::transmute(handler) };
+            handler(event);
+        }
     }
 
-    #[test]
-    fn test_pool_selection() {
-        let smme = SymbianModernMemoryEngine::new(1 << 30);
-        
-        // Small allocation -> L0
-        let small = smme.allocate(1024).unwrap();
-        
-        // Medium allocation -> L1
-        let medium = smme.allocate(128 * 1024).unwrap();
-        
-        // Large allocation -> L2
-        let large = smme.allocate(4 * 1024 * 1024).unwrap();
-        
-        assert!(small != medium);
-        assert!(medium != large);
+    /// Register a hook called after every successful allocation.
+    /// It runs inside the allocation path, so it must not allocate or take locks.
+    pub fn set_allocation_observer(&self, observer: fn(AllocationEvent)) {
+        self.observer.store(observer as *mut (), Ordering::Release);
     }
 
-    #[test]
-    fn test_predictive_cleanup() {
-        let smme = SymbianModernMemoryEngine::new(1 << 30);
-        
-        // Fill memory
-        for _ in 0..10 {
-            let _ = smme.allocate(100 * 1024);
+    fn notify_observer(&self, event: AllocationEvent) {
+        let observer = self.observer.load(Ordering::Acquire);
+        if !observer.is_null() {
+            // Only ever set from a `fn(AllocationEvent)` in `set_allocation_observer`
+            let observer: fn(AllocationEvent) = unsafe { core::mem::transmute(observer) };
+            observer(event);
         }
-        
-        // Trigger cleanup
-        let freed = smme.predictive_cleanup();
-        assert!(freed >= 0);
+    }
+
+    /// Allocations recorded after sequence number `cursor`, oldest first, with the cursor
+    /// for the next call. Only the last `HISTORY_LEN` are kept, so slow readers miss some.
+    pub fn allocations_since(&self, cursor: usize) -> ([Option<AllocationEvent>; HISTORY_LEN], usize) {
+        let mut events = [None; HISTORY_LEN];
+        let next = self.history_index.load(Ordering::Acquire);
+        let first = cursor.max(next.saturating_sub(HISTORY_LEN));
+        for (event, seq) in events.iter_mut().zip(first..next) {
+            *event = AllocationEvent::unpack(self.allocation_history[seq % HISTORY_LEN].load(Ordering::Acquire));
+        }
+        (events, next)
+    }
+
+    /// Most recent allocations, oldest first
+    pub fn recent_allocations(&self) -> [Option<AllocationEvent>; HISTORY_LEN] {
+        let mut events = [None; HISTORY_LEN];
+        let next = self.history_index.load(Ordering::Acquire);
+        for (i, event) in events.iter_mut().enumerate() {
+            let slot = (next + i) % HISTORY_LEN;
+            *event = AllocationEvent::unpack(self.allocation_history[slot].load(Ordering::Acquire));
+        }
+        events
+    }
+
+    /// Release an allocation made by `allocate`
+    pub fn free(&self, cap: Capability) -> Result<(), AllocationError> {
+        self.free_for(KERNEL_OWNER, cap)
+    }
+
+    /// Release an allocation on behalf of `owner`, refunding the holder's quota.
+    /// Every copy of the handle is dead afterwards.
+    pub fn free_for(&self, owner: OwnerId, cap: Capability) -> Result<(), AllocationError> {
+        self.capabilities.revoke(&cap, owner)?;
+        self.free_in_pool(cap.addr(), cap.len(), cap.align(), cap.owner())
+    }
+
+    #[cfg(not(feature = "smme-debug"))]
+    fn free_in_pool(
+        &self,
+        addr: usize,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<(), AllocationError> {
+        self.free_charged(addr, size, align, owner)
+    }
+
+    /// Debug builds check both redzones before the block goes back to its pool;
+    /// damage is reported by the next `check_redzones`
+    #[cfg(feature = "smme-debug")]
+    fn free_in_pool(
+        &self,
+        addr: usize,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<(), AllocationError> {
+        let (padded, _) = RedzoneTracker::padding(size, align)?;
+        // Tracked allocations stay mapped until they are released
+        unsafe {
+            self.redzones.disarm(addr, size, |allocation| {
+                self.free_charged(RedzoneTracker::block_start(allocation), padded, align, owner)
+            })?
+        };
+        self.purgeable.remove(addr);
+        Ok(())
+    }
+
+    fn free_charged(
+        &self,
+        addr: usize,
+        size: usize,
+        align: usize,
+        owner: OwnerId,
+    ) -> Result<(), AllocationError> {
+        self.release_block(addr, size, align)?;
+        if let Some(pool) = self.pool_for_addr(addr) {
+            self.counters[self.pool_id(pool) as usize].record_free();
+        }
+        self.quotas.refund(owner, self.block_size(size, align));
+        self.purgeable.remove(addr);
+        Ok(())
+    }
+
+    fn release_block(&self, addr: usize, size: usize, align: usize) -> Result<(), AllocationError> {
+        match SlabAllocator::class_for(size, align) {
+            Some(class) if self.l0_pool.contains(addr) => {
+                self.l0_slab.free(class, addr, &self.l0_pool)
+            }
+            _ => {
+                let pool = self.pool_for_addr(addr).ok_or(AllocationError::InvalidAddress)?;
+                if !self.uses_buddy(pool) {
+                    return pool.release(addr, size);
+                }
+
+                let order = self.buddy_order(size, align);
+                pool.release(addr, pool.granule() << order)?;
+                self.l2_buddy.free((addr - pool.base) / pool.granule(), order);
+                Ok(())
+            }
+        }
+    }
+
+    /// Bytes actually set aside for a request of this shape
+    fn block_size(&self, size: usize, align: usize) -> usize {
+        match SlabAllocator::class_for(size, align) {
+            Some(class) => SlabAllocator::object_size(class),
+            None if self.uses_buddy(self.pool_for_size(size)) => {
+                self.l2_pool.granule() << self.buddy_order(size, align)
+            }
+            None => self.pool_for_size(size).rounded(size),
+        }
+    }
+
+    /// Keep a block where it is when freeing it at `new_size` would release exactly the
+    /// same block and refund the same charge; commits any pages the new size reaches
+    fn resize_in_place(&self, addr: usize, size: usize, new_size: usize, align: usize) -> bool {
+        // Charges follow the request size, so both sizes must cost the same
+        if !ptr::eq(self.pool_for_size(size), self.pool_for_size(new_size))
+            || self.block_size(size, align) != self.block_size(new_size, align)
+        {
+            return false;
+        }
+
+        // The block may have spilled, so ask the pool that really holds it
+        let class = SlabAllocator::class_for(size, align);
+        let pool = match self.pool_for_addr(addr) {
+            Some(pool) if class == SlabAllocator::class_for(new_size, align) => pool,
+            _ => return false,
+        };
+        let same_block = match class {
+            Some(_) if ptr::eq(pool, &self.l0_pool) => true,
+            _ if self.uses_buddy(pool) => {
+                self.buddy_order(size, align) == self.buddy_order(new_size, align)
+            }
+            _ => pool.rounded(size) == pool.rounded(new_size),
+        };
+        same_block && pool.commit(addr, new_size).is_ok()
+    }
+
+    /// Back more of an allocation with physical pages
+    pub fn commit(
+        &self,
+        accessor: OwnerId,
+        cap: &Capability,
+        offset: usize,
+        size: usize,
+    ) -> Result<(), AllocationError> {
+        let addr = self.access(accessor, cap, offset, size, Rights::WRITE)?;
+        self.pool_for_addr(addr)
+            .ok_or(AllocationError::InvalidAddress)?
+            .commit(addr, size)
+    }
+
+    /// Give back the physical pages of part of an allocation; the addresses stay reserved
+    pub fn decommit(
+        &self,
+        accessor: OwnerId,
+        cap: &Capability,
+        offset: usize,
+        size: usize,
+    ) -> Result<(), AllocationError> {
+        let addr = self.access(accessor, cap, offset, size, Rights::WRITE)?;
+        self.decommit_range(addr, size)
+    }
+
+    /// Commit part of an allocation and map its pages into `space` at the same addresses,
+    /// readable and writable by `accessor`. Mapping works in whole pages, so only the kernel
+    /// may map allocations that share a page with their neighbours, e.g. slab objects.
+    pub fn commit_mapped<F: FrameAllocator>(
+        &self,
+        accessor: OwnerId,
+        cap: &Capability,
+        offset: usize,
+        size: usize,
+        space: &mut AddressSpace,
+        frames: &mut F,
+    ) -> Result<(), AllocationError> {
+        let whole_pages = cap.addr().is_multiple_of(PAGE_SIZE) && cap.len().is_multiple_of(PAGE_SIZE);
+        if accessor != KERNEL_OWNER && !whole_pages {
+            return Err(AllocationError::AccessDenied);
+        }
+        self.commit(accessor, cap, offset, size)?;
+
+        let addr = cap.addr() + offset;
+        let start = addr - addr % PAGE_SIZE;
+        let end = (addr + size).next_multiple_of(PAGE_SIZE);
+        let access = if accessor == KERNEL_OWNER {
+            Access::READ_WRITE
+        } else {
+            Access::READ_WRITE.union(Access::USER)
+        };
+        space.map(frames, start, start, end - start, access)?;
+        Ok(())
+    }
+
+    /// Unmap the pages lying entirely inside the range from `space`, then decommit them
+    pub fn decommit_unmapped<F: FrameAllocator>(
+        &self,
+        accessor: OwnerId,
+        cap: &Capability,
+        offset: usize,
+        size: usize,
+        space: &mut AddressSpace,
+        frames: &mut F,
+    ) -> Result<(), AllocationError> {
+        let addr = self.access(accessor, cap, offset, size, Rights::WRITE)?;
+        let start = addr.next_multiple_of(PAGE_SIZE);
+        let end = (addr + size) - (addr + size) % PAGE_SIZE;
+        if start < end {
+            space.unmap(frames, start, end - start)?;
+        }
+        self.decommit_range(addr, size)
+    }
+
+    fn decommit_range(&self, addr: usize, size: usize) -> Result<(), AllocationError> {
+        self.pool_for_addr(addr)
+            .ok_or(AllocationError::InvalidAddress)?
+            .decommit(addr, size)
+    }
+
+    fn pool_for_addr(&self, addr: usize) -> Option<&MemoryPool> {
+        [&self.l0_pool, &self.l1_pool, &self.l2_pool]
+            .into_iter()
+            .find(|pool| pool.contains(addr))
+    }
+
+    /// Let SMME drop the pages of a cache whose owner can rebuild it under memory pressure
+    pub fn register_purgeable(&self, cap: &Capability) -> Result<(), AllocationError> {
+        let addr = self.access(cap.owner(), cap, 0, cap.len(), Rights::WRITE)?;
+        self.purgeable.register(addr, cap.len())
+    }
+
+    pub fn unregister_purgeable(&self, cap: &Capability) -> Result<(), AllocationError> {
+        self.capabilities.validate(cap, cap.owner(), Rights::WRITE)?;
+        if self.purgeable.remove(cap.addr()) {
+            Ok(())
+        } else {
+            Err(AllocationError::InvalidAddress)
+        }
+    }
+
+    /// Allocate a region `owner` can hand to other active objects, charged to `owner`
+    pub fn create_shared(&self, owner: OwnerId, size: usize) -> Result<SharedHandle, AllocationError> {
+        let align = self.validate_request(size, 1)?;
+        let addr = self.allocate_in_pool(size, align, owner)?;
+        self.shared.create(addr, size, align, owner).inspect_err(|_| {
+            let _ = self.free_in_pool(addr, size, align, owner);
+        })
+    }
+
+    /// Share the region with `to`; read-only grants are copied the first time `to` writes
+    pub fn grant_shared(
+        &self,
+        handle: &SharedHandle,
+        to: OwnerId,
+        rights: Rights,
+    ) -> Result<SharedHandle, AllocationError> {
+        self.shared.grant(handle, to, rights)
+    }
+
+    /// Handle for a `SharedHandle::token` received in a message
+    pub fn shared_from_token(&self, token: u64) -> Result<SharedHandle, AllocationError> {
+        self.shared.redeem(token)
+    }
+
+    /// Address of `offset..offset + len` as the holder sees it.
+    /// Asking for WRITE through a read-only grant gives the holder its own copy first.
+    pub fn shared_addr(
+        &self,
+        handle: &SharedHandle,
+        offset: usize,
+        len: usize,
+        rights: Rights,
+    ) -> Result<usize, AllocationError> {
+        let mut view = self.shared.view(handle)?;
+        let end = offset.checked_add(len).ok_or(AllocationError::InvalidRequest)?;
+        if end > view.size {
+            return Err(AllocationError::InvalidAddress);
+        }
+        if rights.contains(Rights::WRITE) && !view.rights.contains(Rights::WRITE) {
+            view.addr = self.break_shared(handle, &view)?;
+        }
+        Ok(view.addr + offset)
+    }
+
+    pub fn read_shared(
+        &self,
+        handle: &SharedHandle,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<(), AllocationError> {
+        let addr = self.shared_addr(handle, offset, buf.len(), Rights::READ)?;
+        // Shared regions are live blocks in SMME's pools and the range was bounds-checked
+        unsafe { ptr::copy_nonoverlapping(addr as *const u8, buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+
+    pub fn write_shared(
+        &self,
+        handle: &SharedHandle,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<(), AllocationError> {
+        let addr = self.shared_addr(handle, offset, data.len(), Rights::WRITE)?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), addr as *mut u8, data.len()) };
+        Ok(())
+    }
+
+    /// Drop a holder's handle; the region is freed with its last holder
+    pub fn release_shared(&self, handle: SharedHandle) -> Result<(), AllocationError> {
+        match self.shared.release(&handle)? {
+            Some(orphan) => self.free_in_pool(orphan.addr, orphan.size, orphan.align, orphan.owner),
+            None => Ok(()),
+        }
+    }
+
+    /// Copy-on-write: move a read-only holder onto a private copy of the region
+    fn break_shared(&self, handle: &SharedHandle, view: &SharedView) -> Result<usize, AllocationError> {
+        // Nobody left to share with; write in place once the holder pays for the block
+        let charge = self.charged_size(view.size, view.align)?;
+        let claimed = self.shared.claim_sole(handle, view, |owner| {
+            self.quotas.charge(handle.holder(), charge)?;
+            self.quotas.refund(owner, charge);
+            Ok(())
+        })?;
+        if claimed {
+            return Ok(view.addr);
+        }
+
+        let copy = self.allocate_in_pool(view.size, view.align, handle.holder())?;
+        // Both blocks are live and `view.size` long
+        unsafe { ptr::copy_nonoverlapping(view.addr as *const u8, copy as *mut u8, view.size) };
+
+        match self.shared.detach(handle, view, copy) {
+            Ok(orphan) => {
+                if let Some(orphan) = orphan {
+                    self.free_in_pool(orphan.addr, orphan.size, orphan.align, orphan.owner)?;
+                }
+                Ok(copy)
+            }
+            Err(err) => {
+                let _ = self.free_in_pool(copy, view.size, view.align, handle.holder());
+                Err(err)
+            }
+        }
+    }
+
+    /// Symbian-style low-memory notification; the handler gets the number of bytes wanted
+    pub fn register_low_memory_handler(&self, handler: fn(usize)) -> Result<usize, AllocationError> {
+        self.low_memory.register(handler)
+    }
+
+    pub fn unregister_low_memory_handler(&self, slot: usize) {
+        self.low_memory.unregister(slot);
+    }
+
+    /// Pressure level of the fullest pool
+    pub fn pressure(&self) -> MemoryPressure {
+        let worst = [&self.l0_pool, &self.l1_pool, &self.l2_pool]
+            .iter()
+            .map(|pool| pool.usage().1 * 100 / pool.size.max(1))
+            .max()
+            .unwrap_or(0);
+
+        if worst >= CRITICAL_PRESSURE_PCT {
+            MemoryPressure::Critical
+        } else if worst >= WARNING_PRESSURE_PCT {
+            MemoryPressure::Warning
+        } else {
+            MemoryPressure::Normal
+        }
+    }
+
+    /// Let requests go to mesh peers through `transport` once local pools are exhausted
+    pub fn enable_distribution(&self, transport: &'static dyn MemoryTransport) {
+        *self.transport.lock() = Some(transport);
+        self.distributed_enabled.store(true, Ordering::Release);
+    }
+
+    /// Stop placing new remote blocks; existing remote handles keep working
+    pub fn disable_distribution(&self) {
+        self.distributed_enabled.store(false, Ordering::Release);
+    }
+
+    pub fn is_distributed(&self) -> bool {
+        self.distributed_enabled.load(Ordering::Acquire)
+    }
+
+    /// Allocate locally, falling back to a mesh peer when distribution is enabled
+    pub fn allocate_distributed(
+        &self,
+        mesh: &DeviceMesh,
+        size: usize,
+    ) -> Result<MemoryHandle, AllocationError> {
+        match self.allocate(size) {
+            Err(AllocationError::OutOfMemory) if self.is_distributed() => {
+                self.allocate_remote(mesh, size).map(MemoryHandle::Remote)
+            }
+            local => local.map(MemoryHandle::Local),
+        }
+    }
+
+    /// Allocate on the best peer `mesh` knows about
+    pub fn allocate_remote(&self, mesh: &DeviceMesh, size: usize) -> Result<RemoteHandle, AllocationError> {
+        if size == 0 {
+            return Err(AllocationError::InvalidRequest);
+        }
+        if !self.is_distributed() {
+            return Err(AllocationError::OutOfMemory);
+        }
+
+        let transport = self.transport()?;
+        let peer = mesh.find_best_peer(size, 0).ok_or(AllocationError::OutOfMemory)?;
+        let remote_addr = transport.remote_allocate(peer.id, size)?;
+        self.remote.insert(peer.id, remote_addr, size).inspect_err(|_| {
+            let _ = transport.remote_free(peer.id, remote_addr, size);
+        })
+    }
+
+    /// Copy `buf.len()` bytes at `offset` of a remote block into `buf`
+    pub fn read_remote(
+        &self,
+        handle: &RemoteHandle,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<(), AllocationError> {
+        let addr = self.remote_span(handle, offset, buf.len())?;
+        self.transport()?.remote_read(handle.device(), addr, buf)?;
+        Ok(())
+    }
+
+    pub fn write_remote(
+        &self,
+        handle: &RemoteHandle,
+        offset: usize,
+        data: &[u8],
+    ) -> Result<(), AllocationError> {
+        let addr = self.remote_span(handle, offset, data.len())?;
+        self.transport()?.remote_write(handle.device(), addr, data)?;
+        Ok(())
+    }
+
+    /// Give a remote block back to its peer. The handle dies even if the peer
+    /// can't be reached, in which case the block is leaked on the peer's side.
+    pub fn free_remote(&self, handle: RemoteHandle) -> Result<(), AllocationError> {
+        self.remote.remove(&handle)?;
+        self.transport()?
+            .remote_free(handle.device(), handle.remote_addr(), handle.len())?;
+        Ok(())
+    }
+
+    fn transport(&self) -> Result<&'static dyn MemoryTransport, AllocationError> {
+        self.transport.lock().ok_or(AllocationError::RemoteFailure)
+    }
+
+    /// Peer address of `offset..offset + len` inside a live remote block
+    fn remote_span(&self, handle: &RemoteHandle, offset: usize, len: usize) -> Result<usize, AllocationError> {
+        self.remote.validate(handle)?;
+        let end = offset.checked_add(len).ok_or(AllocationError::InvalidRequest)?;
+        if end > handle.len() {
+            return Err(AllocationError::InvalidAddress);
+        }
+        Ok(handle.remote_addr() + offset)
+    }
+
+    /// Damaged redzone found by a free since the last call, or the first among
+    /// live allocations; run from `kernel_tick`
+    #[cfg(feature = "smme-debug")]
+    pub fn check_redzones(&self) -> Option<Corruption> {
+        // Tracked allocations are live blocks in SMME's own pools
+        unsafe { self.redzones.check_all() }
+    }
+
+    /// Emit the pressure level if it changed since the last poll
+    pub fn poll_pressure(&self) -> Option<MemoryPressure> {
+        let level = self.pressure();
+        let previous = self.last_pressure.swap(level as u8, Ordering::AcqRel);
+        if previous != level as u8 {
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    /// Predictive cleanup (Oracle Engine integration point)
+    pub fn predictive_cleanup(&self) -> usize {
+        // Bring every pool back under 80% committed
+        let wanted: usize = [&self.l0_pool, &self.l1_pool, &self.l2_pool]
+            .iter()
+            .map(|pool| pool.usage().1.saturating_sub(pool.size * WARNING_PRESSURE_PCT / 100))
+            .sum();
+
+        if wanted > 0 {
+            // Trigger cleanup - in v0.5 this will use ML
+            self.reclaim(wanted)
+        } else {
+            0
+        }
+    }
+
+    /// Emergency cleanup (Symbian DNA)
+    pub fn emergency_cleanup(&self) -> usize {
+        self.reclaim(usize::MAX)
+    }
+
+    /// Reclaim in order of increasing cost until `wanted` bytes are back.
+    /// Returns the bytes actually released or decommitted.
+    fn reclaim(&self, wanted: usize) -> usize {
+        let start = self.footprint();
+
+        // 1. Blocks reserved ahead of demand by the reservoir, and empty slab pages
+        let (target, blocks, count) = self.reservoir.retarget(0);
+        for &block in &blocks[..count] {
+            let _ = self.release_block(block, target, 1);
+        }
+        self.l0_slab.trim(&self.l0_pool);
+        if self.released_since(start) >= wanted {
+            return self.released_since(start);
+        }
+
+        // 2. Purgeable caches lose their pages but keep their addresses
+        for (addr, size) in self.purgeable.snapshot() {
+            if addr != 0 {
+                let _ = self.decommit_range(addr, size);
+                if self.released_since(start) >= wanted {
+                    return self.released_since(start);
+                }
+            }
+        }
+
+        // 3. Ask owners to shed memory
+        self.low_memory
+            .notify(wanted, || self.released_since(start));
+
+        self.released_since(start)
+    }
+
+    fn footprint(&self) -> (usize, usize) {
+        [&self.l0_pool, &self.l1_pool, &self.l2_pool]
+            .iter()
+            .map(|pool| pool.usage())
+            .fold((0, 0), |acc, (res, com)| (acc.0 + res, acc.1 + com))
+    }
+
+    /// Bytes given back since `before`, counting either released reservations or dropped pages
+    fn released_since(&self, before: (usize, usize)) -> usize {
+        let (reserved, committed) = self.footprint();
+        before
+            .0
+            .saturating_sub(reserved)
+            .max(before.1.saturating_sub(committed))
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        let (l0_res, l0_com) = self.l0_pool.usage();
+        let (l1_res, l1_com) = self.l1_pool.usage();
+        let (l2_res, l2_com) = self.l2_pool.usage();
+
+        let granule = self.l2_pool.granule();
+        let buddy = self.l2_buddy.stats();
+        let (l2_largest_free, l2_free) = if self.l2_buddy_enabled.load(Ordering::Acquire) {
+            (buddy.largest_free_granules * granule, buddy.free_granules * granule)
+        } else {
+            let capacity = self.l2_pool.granule_count() * granule;
+            (self.l2_pool.largest_free_run() * granule, capacity.saturating_sub(l2_res))
+        };
+
+        let (remote_count, remote_bytes) = self.remote.usage();
+
+        MemoryStats {
+            total_reserved: l0_res + l1_res + l2_res,
+            total_committed: l0_com + l1_com + l2_com,
+            l0_usage: l0_com,
+            l1_usage: l1_com,
+            l2_usage: l2_com,
+            l0_reserved: l0_res,
+            l1_reserved: l1_res,
+            l2_reserved: l2_res,
+            slab_classes: self.l0_slab.stats(),
+            l2_largest_free,
+            l2_fragmentation: (l2_largest_free * 100)
+                .checked_div(l2_free)
+                .map_or(0, |largest_pct| 100 - largest_pct),
+            buddy_splits: buddy.splits,
+            buddy_merges: buddy.merges,
+            reservoir: self.reservoir.stats(),
+            owners: self.quotas.snapshot(),
+            l0_exhausted: self.counters[PoolId::L0 as usize].failures() as usize,
+            l1_exhausted: self.counters[PoolId::L1 as usize].failures() as usize,
+            l2_exhausted: self.counters[PoolId::L2 as usize].failures() as usize,
+            spills: self.spills.load(Ordering::Relaxed),
+            remote_allocations: remote_count,
+            remote_bytes,
+        }
+    }
+
+    /// Plain-data statistics for the C API and monitoring agents
+    pub fn snapshot(&self) -> MemoryStatsSnapshot {
+        let mut pools = [PoolStats::empty(); 3];
+        for (id, pool) in [&self.l0_pool, &self.l1_pool, &self.l2_pool].into_iter().enumerate() {
+            let (reserved, committed) = pool.usage();
+            let out = &mut pools[id];
+            out.base = pool.base;
+            out.size = pool.size;
+            out.reserved = reserved;
+            out.committed = committed;
+            out.free = pool.size.saturating_sub(reserved);
+            out.peak_committed = pool.peak_committed();
+            self.counters[id].export(out);
+        }
+
+        let (remote_allocations, remote_bytes) = self.remote.usage();
+        MemoryStatsSnapshot {
+            version: STATS_VERSION,
+            total_reserved: pools.iter().map(|p| p.reserved).sum(),
+            total_committed: pools.iter().map(|p| p.committed).sum(),
+            pools,
+            spills: self.spills.load(Ordering::Relaxed),
+            remote_allocations,
+            remote_bytes,
+        }
+    }
+}
+
+/// Lets kernel code use `alloc` collections on top of SMME.
+/// Collections reach `alloc` through the compiler's allocator shim, which can't pass on a
+/// caller location, so redzone reports name this impl as their allocation site.
+unsafe impl GlobalAlloc for SymbianModernMemoryEngine {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match self.allocate_in_pool(layout.size(), layout.align(), KERNEL_OWNER) {
+            Ok(addr) => addr as *mut u8,
+            Err(_) => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = self.free_in_pool(ptr as usize, layout.size(), layout.align(), KERNEL_OWNER);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Debug builds always move so the back redzone follows the new size
+        if !cfg!(feature = "smme-debug")
+            && self.resize_in_place(ptr as usize, layout.size(), new_size, layout.align())
+        {
+            return ptr;
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryStats {
+    pub total_reserved: usize,
+    pub total_committed: usize,
+    pub l0_usage: usize,
+    pub l1_usage: usize,
+    pub l2_usage: usize,
+    pub l0_reserved: usize,
+    pub l1_reserved: usize,
+    pub l2_reserved: usize,
+    pub slab_classes: [SlabClassStats; SLAB_CLASSES.len()],
+    /// Largest block L2 can still hand out, in bytes
+    pub l2_largest_free: usize,
+    /// Percentage of free L2 memory outside the largest free block
+    pub l2_fragmentation: usize,
+    pub buddy_splits: usize,
+    pub buddy_merges: usize,
+    pub reservoir: ReservoirStats,
+    pub owners: [Option<OwnerUsage>; MAX_OWNERS],
+    /// Times each pool could not satisfy a request on its own
+    pub l0_exhausted: usize,
+    pub l1_exhausted: usize,
+    pub l2_exhausted: usize,
+    /// Allocations served by a larger pool than their size asked for
+    pub spills: usize,
+    /// Blocks held on mesh peers, and their total size
+    pub remote_allocations: usize,
+    pub remote_bytes: usize,
+}
+
+/// Memory pressure levels reported to active objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MemoryPressure {
+    Normal = 0,
+    Warning = 1,
+    Critical = 2,
+}
+
+/// Pool that served an allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PoolId {
+    L0 = 0,
+    L1 = 1,
+    L2 = 2,
+}
+
+/// Where `allocate_distributed` placed a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryHandle {
+    Local(Capability),
+    Remote(RemoteHandle),
+}
+
+/// What to do when the pool matching a request's size is exhausted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SpillPolicy {
+    /// Fail with `OutOfMemory`
+    Never = 0,
+    /// Try the next larger pool
+    Upward = 1,
+    /// Spill upward and report each spill to the spill handler
+    Warn = 2,
+}
+
+impl SpillPolicy {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => SpillPolicy::Upward,
+            2 => SpillPolicy::Warn,
+            _ => SpillPolicy::Never,
+        }
+    }
+}
+
+/// A request that spilled out of its own pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpillEvent {
+    pub size: usize,
+    pub from: PoolId,
+    pub to: PoolId,
+}
+
+/// One entry of the allocation history ring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocationEvent {
+    pub size: usize,
+    pub pool: PoolId,
+}
+
+impl AllocationEvent {
+    // Low byte holds pool id + 1 so an empty slot reads as zero
+    fn pack(&self) -> u64 {
+        ((self.size as u64) << 8) | (self.pool as u64 + 1)
+    }
+
+    fn unpack(raw: u64) -> Option<Self> {
+        let pool = match raw & 0xff {
+            1 => PoolId::L0,
+            2 => PoolId::L1,
+            3 => PoolId::L2,
+            _ => return None,
+        };
+        Some(Self { size: (raw >> 8) as usize, pool })
+    }
+}
+
+/// Placement policy for the L2 large pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L2Allocator {
+    FirstFit,
+    Buddy,
+}
+
+#[derive(Debug)]
+pub enum AllocationError {
+    OutOfMemory,
+    InvalidAddress,
+    InvalidRequest,
+    /// The owner's memory budget does not cover the request
+    QuotaExceeded,
+    /// The capability refers to an allocation that was already freed
+    UseAfterFree,
+    /// Forged handle, wrong owner or missing rights
+    AccessDenied,
+    /// The bus or the peer holding remote memory failed the request
+    RemoteFailure,
+}
+
+/// SMME frames back the kernel's translation tables; its pools are identity mapped
+impl FrameAllocator for &SymbianModernMemoryEngine {
+    fn allocate_frame(&mut self) -> Option<usize> {
+        // Tables must be exactly one page, so they never get redzones
+        self.allocate_charged(PAGE_SIZE, PAGE_SIZE, KERNEL_OWNER).ok()
+    }
+
+    fn free_frame(&mut self, frame: usize) {
+        let _ = self.free_charged(frame, PAGE_SIZE, PAGE_SIZE, KERNEL_OWNER);
+    }
+
+    fn table(&mut self, frame: usize) -> &mut [u64; ENTRIES] {
+        // Frames are live, page-aligned blocks of SMME's own pools
+        unsafe { &mut *(frame as *mut [u64; ENTRIES]) }
+    }
+}
+
+impl From<PagingError> for AllocationError {
+    fn from(err: PagingError) -> Self {
+        match err {
+            PagingError::OutOfFrames => AllocationError::OutOfMemory,
+            PagingError::Misaligned | PagingError::InvalidAccess => AllocationError::InvalidRequest,
+            PagingError::InvalidAddress | PagingError::AlreadyMapped | PagingError::NotMapped => {
+                AllocationError::InvalidAddress
+            }
+        }
+    }
+}
+
+impl From<BusError> for AllocationError {
+    fn from(_: BusError) -> Self {
+        AllocationError::RemoteFailure
+    }
+}
+
+// Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::reservoir::RESERVOIR_SLOTS;
+    use crate::bus::transport::LoopbackTransport;
+    use crate::bus::Device;
+    use crate::memory::stats::bucket_for;
+    use std::sync::OnceLock;
+
+    /// Redzone bytes debug builds add to every request; tests that fill whole pages ask for less
+    #[cfg(feature = "smme-debug")]
+    const PAD: usize = 2 * crate::memory::redzone::REDZONE_SIZE;
+    #[cfg(not(feature = "smme-debug"))]
+    const PAD: usize = 0;
+    /// L2 blocks are page aligned, so their front redzone is a whole page
+    #[cfg(feature = "smme-debug")]
+    const L2_PAD: usize = PAGE_SIZE + crate::memory::redzone::REDZONE_SIZE;
+    #[cfg(not(feature = "smme-debug"))]
+    const L2_PAD: usize = 0;
+
+    #[test]
+    fn test_two_phase_allocation() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        
+        // Allocate 1MB
+        let cap = smme.allocate(1024 * 1024).unwrap();
+        assert!(cap.addr() > 0);
+        
+        // Check stats
+        let stats = smme.stats();
+        assert!(stats.total_committed > 0);
+    }
+
+    #[test]
+    fn test_pool_selection() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        
+        // Small allocation -> L0
+        let small = smme.allocate(1024).unwrap();
+        
+        // Medium allocation -> L1
+        let medium = smme.allocate(128 * 1024).unwrap();
+        
+        // Large allocation -> L2
+        let large = smme.allocate(4 * 1024 * 1024).unwrap();
+        
+        assert!(small.addr() != medium.addr());
+        assert!(medium.addr() != large.addr());
+
+        let stats = smme.stats();
+        assert!(stats.l0_reserved > 0 && stats.l1_reserved >= 128 * 1024);
+        assert!(stats.l2_reserved >= 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_predictive_cleanup() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        
+        // Fill memory
+        for _ in 0..10 {
+            let _ = smme.allocate(100 * 1024);
+        }
+        
+        // Trigger cleanup
+        let freed = smme.predictive_cleanup();
+        assert!(freed >= 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "smme-debug", ignore = "redzones move allocations off page boundaries")]
+    fn test_emergency_cleanup_reclaims() {
+        static SMME: OnceLock<SymbianModernMemoryEngine> = OnceLock::new();
+        static OWNED: SpinLock<Option<Capability>> = SpinLock::new(None);
+        fn shed(_wanted: usize) {
+            if let Some(cap) = OWNED.lock().take() {
+                SMME.get().unwrap().free(cap).unwrap();
+            }
+        }
+
+        let smme = SMME.get_or_init(SymbianModernMemoryEngine::host_backed);
+        smme.refill_reservoir(128 * 1024);
+        let cache = smme.allocate(512 * 1024).unwrap();
+        smme.register_purgeable(&cache).unwrap();
+        *OWNED.lock() = Some(smme.allocate(256 * 1024).unwrap());
+        let handler = smme.register_low_memory_handler(shed).unwrap();
+
+        let freed = smme.emergency_cleanup();
+        assert_eq!(freed, RESERVOIR_SLOTS * 128 * 1024 + 512 * 1024 + 256 * 1024);
+
+        // The purged cache is still reserved, just not backed
+        let stats = smme.stats();
+        assert_eq!(stats.l1_reserved, 512 * 1024);
+        assert_eq!(stats.l1_usage, 0);
+        assert_eq!(smme.emergency_cleanup(), 0);
+
+        // Unregistered handlers are no longer asked
+        smme.unregister_low_memory_handler(handler);
+        *OWNED.lock() = Some(smme.allocate(256 * 1024).unwrap());
+        assert_eq!(smme.emergency_cleanup(), 0);
+        assert!(OWNED.lock().is_some());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "smme-debug", ignore = "redzones move allocations off page boundaries")]
+    fn test_predictive_cleanup_stops_when_relieved() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let cache = smme.allocate(1024 * 1024).unwrap();
+        smme.register_purgeable(&cache).unwrap();
+        let _live = smme.allocate(768 * 1024).unwrap();
+
+        // 1.75MB of 2MB committed; purging the cache brings L1 back under 80%
+        assert_eq!(smme.predictive_cleanup(), 1024 * 1024);
+        assert_eq!(smme.predictive_cleanup(), 0);
+
+        // Freed allocations drop their purgeable registration
+        smme.free(cache).unwrap();
+        assert!(smme.unregister_purgeable(&cache).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(feature = "smme-debug", ignore = "redzones move allocations off page boundaries")]
+    fn test_predictive_cleanup_purges_only_what_it_needs() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let caches = [(); 3].map(|_| smme.allocate(512 * 1024).unwrap());
+        for cache in &caches {
+            smme.register_purgeable(cache).unwrap();
+        }
+        let _live = smme.allocate(256 * 1024).unwrap();
+
+        // 1.75MB of 2MB committed; one cache is more than enough to get under 80%
+        assert_eq!(smme.predictive_cleanup(), 512 * 1024);
+        let kept = caches.iter().filter(|c| smme.l1_pool.is_committed(c.addr())).count();
+        assert_eq!(kept, 2);
+        assert_eq!(smme.stats().l1_usage, 1280 * 1024);
+    }
+
+    #[test]
+    fn test_pressure_levels() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        assert_eq!(smme.poll_pressure(), None);
+
+        let a = smme.allocate(1700 * 1024).unwrap();
+        assert_eq!(smme.poll_pressure(), Some(MemoryPressure::Warning));
+        assert_eq!(smme.poll_pressure(), None);
+
+        let b = smme.allocate(300 * 1024).unwrap();
+        assert_eq!(smme.poll_pressure(), Some(MemoryPressure::Critical));
+
+        smme.free(a).unwrap();
+        smme.free(b).unwrap();
+        assert_eq!(smme.poll_pressure(), Some(MemoryPressure::Normal));
+    }
+
+    #[test]
+    fn test_owner_quota_enforced() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        smme.set_quota(42, 1024 * 1024).unwrap();
+
+        let a = smme.allocate_for(42, 600 * 1024 - PAD).unwrap();
+        assert!(matches!(
+            smme.allocate_for(42, 600 * 1024),
+            Err(AllocationError::QuotaExceeded)
+        ));
+        // Other owners are unaffected
+        let b = smme.allocate_for(7, 600 * 1024).unwrap();
+
+        let usage = smme.owner_usage(42).unwrap();
+        assert_eq!((usage.used, usage.limit), (600 * 1024, 1024 * 1024));
+        // Owners without a quota don't take a slot in the table
+        assert!(smme.owner_usage(7).is_none());
+        assert!(smme.stats().owners.iter().flatten().all(|o| o.owner == 42));
+
+        smme.free_for(42, a).unwrap();
+        smme.free_for(7, b).unwrap();
+        assert_eq!(smme.owner_usage(42).unwrap().used, 0);
+        assert_eq!(smme.owner_usage(42).unwrap().peak, 600 * 1024);
+
+        // Small objects are charged their slab class
+        let obj = smme.allocate_for(42, 50 - PAD).unwrap();
+        assert_eq!(smme.owner_usage(42).unwrap().used, 64);
+        smme.free_for(42, obj).unwrap();
+    }
+
+    #[test]
+    fn test_capability_checks() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let cap = smme.allocate_for(3, 8192).unwrap();
+        assert_eq!(smme.access(3, &cap, 4096, 4096, Rights::WRITE).unwrap(), cap.addr() + 4096);
+        assert!(smme.access(3, &cap, 4096, 4097, Rights::READ).is_err());
+
+        // Another owner cannot touch or free the allocation
+        assert!(matches!(smme.access(4, &cap, 0, 1, Rights::READ), Err(AllocationError::AccessDenied)));
+        assert!(matches!(smme.free_for(4, cap), Err(AllocationError::AccessDenied)));
+
+        // Read-only handles cannot change backing or free
+        let read_only = smme.restrict(&cap, Rights::READ).unwrap();
+        assert!(smme.decommit(3, &read_only, 0, 4096).is_err());
+        assert!(smme.free_for(3, read_only).is_err());
+
+        smme.free_for(3, cap).unwrap();
+        // Every copy of the handle is dead, even once the range is reused
+        let reused = smme.allocate_for(3, 8192).unwrap();
+        assert_eq!(reused.addr(), cap.addr());
+        assert!(matches!(smme.access(3, &cap, 0, 1, Rights::READ), Err(AllocationError::UseAfterFree)));
+        assert!(matches!(
+            smme.access(3, &read_only, 0, 1, Rights::READ),
+            Err(AllocationError::UseAfterFree)
+        ));
+    }
+
+    #[test]
+    fn test_allocate_aligned() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        for (size, align) in [(0, 8), (64, 0), (64, 48), (usize::MAX - 8, 8)] {
+            assert!(matches!(
+                smme.allocate_aligned(size, align),
+                Err(AllocationError::InvalidRequest)
+            ));
+        }
+        assert!(matches!(smme.allocate(1 << 40), Err(AllocationError::OutOfMemory)));
+
+        let object = smme.allocate_aligned(24, 64).unwrap();
+        let frame = smme.allocate_aligned(4096, 16 * 1024).unwrap();
+        let dma = smme.allocate_aligned(128 * 1024, 1024 * 1024).unwrap();
+        let large = smme.allocate(3 * 1024 * 1024).unwrap();
+        assert_eq!(object.addr() % 64, 0);
+        assert_eq!(frame.addr() % (16 * 1024), 0);
+        assert_eq!(dma.addr() % (1024 * 1024), 0);
+        // L2 blocks are page aligned whatever was asked for
+        assert_eq!((large.addr() % PAGE_SIZE, large.align()), (0, PAGE_SIZE));
+
+        for cap in [object, frame, dma, large] {
+            smme.free(cap).unwrap();
+        }
+        smme.l0_slab.trim(&smme.l0_pool);
+        assert_eq!(smme.stats().total_reserved, 0);
+    }
+
+    #[test]
+    fn test_reservation_overflow_is_rejected() {
+        let pool = MemoryPool::new(0x2000_0000, L1_POOL_SIZE);
+        let addr = pool.reserve(PAGE_SIZE).unwrap();
+
+        assert!(matches!(pool.reserve(usize::MAX), Err(AllocationError::OutOfMemory)));
+        assert!(pool.reserve_at(usize::MAX / 2, PAGE_SIZE).is_err());
+        assert!(pool.reserve_at(1, usize::MAX - PAGE_SIZE).is_err());
+        assert!(pool.commit(addr, usize::MAX).is_err());
+        assert!(pool.release(addr, usize::MAX).is_err());
+        assert_eq!(pool.usage().0, PAGE_SIZE);
+
+        pool.release(addr, PAGE_SIZE).unwrap();
+        assert_eq!(pool.usage().0, 0);
+    }
+
+    #[test]
+    fn test_concurrent_reservations_never_overcommit() {
+        const THREADS: usize = 8;
+        const ROUNDS: usize = 20_000;
+
+        // 256 granules, far fewer than the threads want between them
+        let pool = MemoryPool::new(0x2000_0000, 1024 * 1024);
+        // Granules each thread believes it owns; a double claim flips a bit twice
+        let owned: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+        let peak = AtomicUsize::new(0);
+        let running = AtomicUsize::new(THREADS);
+        let start = std::sync::Barrier::new(THREADS + 1);
+
+        let flip = |addr: usize, size: usize, claim: bool| {
+            let first = (addr - pool.base) / pool.granule();
+            for g in first..first + size / pool.granule() {
+                let was = owned[g / 64].fetch_xor(1 << (g % 64), Ordering::AcqRel);
+                assert_eq!(was & (1 << (g % 64)) != 0, !claim, "granule {} claimed twice", g);
+            }
+        };
+
+        std::thread::scope(|scope| {
+            // Watches the counter the whole time, catching even a brief overshoot
+            scope.spawn(|| {
+                start.wait();
+                while running.load(Ordering::Acquire) > 0 {
+                    peak.fetch_max(pool.usage().0, Ordering::Relaxed);
+                }
+            });
+
+            for thread in 0..THREADS {
+                let (pool, flip, running, start) = (&pool, &flip, &running, &start);
+                scope.spawn(move || {
+                    start.wait();
+                    let mut held = Vec::new();
+                    for round in 0..ROUNDS {
+                        let size = ((thread * 7 + round) % 16 + 1) * PAGE_SIZE;
+                        if let Ok(addr) = pool.reserve(size) {
+                            flip(addr, size, true);
+                            held.push((addr, size));
+                        }
+
+                        if held.len() > 4 || (round % 3 == 0 && !held.is_empty()) {
+                            let (addr, size) = held.remove(0);
+                            flip(addr, size, false);
+                            pool.release(addr, size).unwrap();
+                        }
+                    }
+                    for (addr, size) in held {
+                        flip(addr, size, false);
+                        pool.release(addr, size).unwrap();
+                    }
+                    running.fetch_sub(1, Ordering::Release);
+                });
+            }
+        });
+
+        // Contention really did fill the pool without ever overshooting it
+        let peak = peak.load(Ordering::Relaxed);
+        assert!(peak > pool.size / 2 && peak <= pool.size, "peak reservation {}", peak);
+        assert_eq!(pool.usage(), (0, 0));
+        assert_eq!(pool.largest_free_run(), pool.granule_count());
+    }
+
+    #[test]
+    fn test_spill_policy() {
+        static WARNED: AtomicUsize = AtomicUsize::new(0);
+        fn warn(event: SpillEvent) {
+            assert_eq!((event.from, event.to), (PoolId::L0, PoolId::L1));
+            WARNED.fetch_add(event.size, Ordering::Relaxed);
+        }
+
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let mut pages = [Capability::null(); 16];
+        for page in pages.iter_mut() {
+            *page = smme.allocate(4096 - PAD).unwrap();
+        }
+
+        // Default policy keeps the old behaviour
+        assert!(matches!(smme.allocate(1024), Err(AllocationError::OutOfMemory)));
+        assert_eq!((smme.stats().l0_exhausted, smme.stats().spills), (1, 0));
+
+        smme.set_spill_policy(SpillPolicy::Upward);
+        let spilled = smme.allocate(1024).unwrap();
+        assert_eq!(smme.pool_for_addr(spilled.addr()).map(|p| smme.pool_id(p)), Some(PoolId::L1));
+        assert_eq!(smme.recent_allocations()[HISTORY_LEN - 1].unwrap().pool, PoolId::L1);
+
+        smme.set_spill_policy(SpillPolicy::Warn);
+        smme.set_spill_handler(warn);
+        let warned = smme.allocate(2000).unwrap();
+        assert_eq!(WARNED.load(Ordering::Relaxed), 2000 + PAD);
+
+        let stats = smme.stats();
+        assert_eq!((stats.l0_exhausted, stats.l1_exhausted, stats.l2_exhausted), (3, 0, 0));
+        assert_eq!(stats.spills, 2);
+
+        // Spilled blocks free back to the pool that holds them
+        smme.free(spilled).unwrap();
+        smme.free(warned).unwrap();
+        assert_eq!(smme.stats().l1_reserved, 0);
+    }
+
+    #[test]
+    fn test_remote_allocation_over_loopback() {
+        static PEER: LoopbackTransport = LoopbackTransport::new(7);
+
+        let mut mesh = DeviceMesh::new();
+        mesh.discover();
+        let mut peer = Device::new(7);
+        peer.available_memory = 64 * 1024;
+        peer.compute_power = 50;
+        peer.latency_ms = 2;
+        mesh.register_device(peer).unwrap();
+
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let mut pages = [Capability::null(); 16];
+        for page in pages.iter_mut() {
+            *page = smme.allocate(4096 - PAD).unwrap();
+        }
+
+        // L0 is full and nothing may leave the board yet
+        assert!(matches!(
+            smme.allocate_distributed(&mesh, 4096),
+            Err(AllocationError::OutOfMemory)
+        ));
+
+        smme.enable_distribution(&PEER);
+        let Ok(MemoryHandle::Remote(handle)) = smme.allocate_distributed(&mesh, 4096) else {
+            panic!("expected a remote block");
+        };
+        assert_eq!((handle.device(), handle.len()), (7, 4096));
+        assert!(!handle.is_empty());
+        assert_eq!((smme.stats().remote_allocations, smme.stats().remote_bytes), (1, 4096));
+
+        smme.write_remote(&handle, 100, b"quantum").unwrap();
+        let mut buf = [0u8; 7];
+        smme.read_remote(&handle, 100, &mut buf).unwrap();
+        assert_eq!(&buf, b"quantum");
+        assert!(matches!(
+            smme.read_remote(&handle, 4090, &mut buf),
+            Err(AllocationError::InvalidAddress)
+        ));
+
+        // Turning distribution off keeps existing blocks reachable
+        smme.disable_distribution();
+        assert!(smme.allocate_distributed(&mesh, 4096).is_err());
+        smme.read_remote(&handle, 100, &mut buf).unwrap();
+
+        smme.free_remote(handle).unwrap();
+        assert!(matches!(
+            smme.write_remote(&handle, 0, b"x"),
+            Err(AllocationError::UseAfterFree)
+        ));
+        assert_eq!(smme.stats().remote_allocations, 0);
+    }
+
+    #[test]
+    fn test_stats_snapshot() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let small = smme.allocate(24).unwrap();
+        let page = smme.allocate(4096 - PAD).unwrap();
+        let medium = smme.allocate(300 * 1024 - PAD).unwrap();
+        smme.free(page).unwrap();
+        assert!(smme.allocate(32 * 1024 * 1024).is_err());
+
+        let snapshot = smme.snapshot();
+        assert_eq!(snapshot.version, STATS_VERSION);
+        let [l0, l1, l2] = snapshot.pools;
+        assert_eq!((l0.allocations, l0.frees, l0.failures), (2, 1, 0));
+        assert_eq!(l0.histogram[bucket_for(24 + PAD)], 1);
+        assert_eq!(l0.histogram[bucket_for(4096)], 1);
+        // The freed page still counts towards the peak
+        assert_eq!((l0.committed, l0.peak_committed), (4096, 2 * 4096));
+        assert_eq!(l1.free, L1_POOL_SIZE - 300 * 1024);
+        assert_eq!(l1.histogram.iter().sum::<u64>(), 1);
+        assert_eq!(l2.allocations, 0);
+        assert_eq!(snapshot.total_committed, 4096 + 300 * 1024);
+
+        smme.free(small).unwrap();
+        smme.free(medium).unwrap();
+    }
+
+    #[test]
+    fn test_free_reuses_range() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        let first = smme.allocate(4096).unwrap();
+        let second = smme.allocate(4096).unwrap();
+        smme.free(first).unwrap();
+
+        // Freed hole is reused before the rest of the pool
+        let third = smme.allocate(3000).unwrap();
+        assert_eq!(third.addr(), first.addr());
+        assert!(second.addr() != third.addr());
+
+        smme.free(second).unwrap();
+        smme.free(third).unwrap();
+        let stats = smme.stats();
+        assert_eq!(stats.total_reserved, 0);
+        assert_eq!(stats.total_committed, 0);
+    }
+
+    #[test]
+    fn test_free_rejects_invalid() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        let cap = smme.allocate(128 * 1024).unwrap();
+        assert!(smme.free(Capability::null()).is_err());
+
+        smme.free(cap).unwrap();
+        // Double free is detected
+        assert!(matches!(smme.free(cap), Err(AllocationError::UseAfterFree)));
+    }
+
+    #[test]
+    fn test_full_pool_recovers_after_free() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        let mut caps = [Capability::null(); 16];
+        for cap in caps.iter_mut() {
+            *cap = smme.allocate(4096 - PAD).unwrap();
+        }
+        assert!(smme.allocate(4096 - PAD).is_err());
+
+        smme.free(caps[7]).unwrap();
+        assert_eq!(smme.allocate(4096 - PAD).unwrap().addr(), caps[7].addr());
+    }
+
+    #[test]
+    fn test_small_objects_use_slabs() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        let a = smme.allocate(56 - PAD).unwrap();
+        let b = smme.allocate(50 - PAD).unwrap();
+        assert_eq!(b.addr(), a.addr() + 64);
+
+        // One slab page backs both objects
+        let stats = smme.stats();
+        assert_eq!(stats.l0_usage, 4096);
+        assert_eq!(stats.slab_classes[2].object_size, 64);
+        assert_eq!(stats.slab_classes[2].in_use, 2);
+
+        smme.free(a).unwrap();
+        smme.free(b).unwrap();
+        // The empty page stays cached until memory gets tight
+        assert_eq!(smme.stats().l0_usage, 4096);
+        smme.emergency_cleanup();
+        assert_eq!(smme.stats().l0_usage, 0);
+    }
+
+    #[test]
+    fn test_global_alloc_alignment() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        unsafe {
+            let small = smme.alloc(Layout::from_size_align(24, 8).unwrap());
+            let page = smme.alloc(Layout::from_size_align(100, 4096).unwrap());
+            let large = smme.alloc(Layout::from_size_align(3 << 20, 1 << 20).unwrap());
+
+            assert!(!small.is_null() && !page.is_null() && !large.is_null());
+            assert_eq!(page as usize % 4096, 0);
+            assert_eq!(large as usize % (1 << 20), 0);
+
+            smme.dealloc(page, Layout::from_size_align(100, 4096).unwrap());
+            smme.dealloc(small, Layout::from_size_align(24, 8).unwrap());
+            smme.dealloc(large, Layout::from_size_align(3 << 20, 1 << 20).unwrap());
+        }
+
+        smme.l0_slab.trim(&smme.l0_pool);
+        assert_eq!(smme.stats().total_reserved, 0);
+    }
+
+    #[test]
+    fn test_l2_buddy_coalesces() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        smme.select_l2_allocator(L2Allocator::Buddy).unwrap();
+
+        let a = smme.allocate(3 * 1024 * 1024 - L2_PAD).unwrap();
+        let b = smme.allocate(3 * 1024 * 1024 - L2_PAD).unwrap();
+        // 3MB rounds up to a 4MB buddy block
+        assert_eq!(b.addr() - a.addr(), 4 * 1024 * 1024);
+        assert!(smme.select_l2_allocator(L2Allocator::FirstFit).is_err());
+
+        let stats = smme.stats();
+        assert_eq!(stats.l2_usage, 6 * 1024 * 1024);
+        assert_eq!(stats.l2_largest_free, 8 * 1024 * 1024);
+        assert_eq!(stats.l2_fragmentation, 0);
+
+        smme.free(a).unwrap();
+        assert_eq!(smme.stats().l2_fragmentation, 34);
+
+        smme.free(b).unwrap();
+        let stats = smme.stats();
+        assert_eq!(stats.l2_largest_free, 16 * 1024 * 1024);
+        assert_eq!(stats.buddy_merges, stats.buddy_splits);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "smme-debug", ignore = "redzones move allocations off page boundaries")]
+    fn test_partial_commit_and_decommit() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let cap = smme.allocate(512 * 1024).unwrap();
+        assert_eq!(smme.stats().l1_usage, 512 * 1024);
+
+        // Dropping a middle range leaves the pages around it committed
+        smme.decommit(KERNEL_OWNER, &cap, 64 * 1024, 128 * 1024).unwrap();
+        assert_eq!(smme.stats().l1_usage, 384 * 1024);
+        assert_eq!(smme.stats().l1_reserved, 512 * 1024);
+
+        // Partially covered pages are kept
+        smme.decommit(KERNEL_OWNER, &cap, 100, PAGE_SIZE).unwrap();
+        assert_eq!(smme.stats().l1_usage, 384 * 1024);
+
+        // Recommitting only counts pages that were actually missing
+        smme.commit(KERNEL_OWNER, &cap, 0, 128 * 1024).unwrap();
+        assert_eq!(smme.stats().l1_usage, 448 * 1024);
+
+        // Nothing outside the allocation can be committed
+        assert!(smme.commit(KERNEL_OWNER, &cap, 512 * 1024, PAGE_SIZE).is_err());
+
+        smme.free(cap).unwrap();
+        assert_eq!(smme.stats().l1_usage, 0);
+    }
+
+    #[test]
+    fn test_allocation_history_and_observer() {
+        static SEEN: AtomicUsize = AtomicUsize::new(0);
+        fn observer(event: AllocationEvent) {
+            SEEN.fetch_add(event.size, Ordering::Relaxed);
+        }
+
+        let smme = SymbianModernMemoryEngine::host_backed();
+        smme.set_allocation_observer(observer);
+
+        smme.allocate(100).unwrap();
+        smme.allocate(300 * 1024).unwrap();
+        smme.allocate(3 * 1024 * 1024).unwrap();
+
+        assert_eq!(SEEN.load(Ordering::Relaxed), 100 + 300 * 1024 + 3 * 1024 * 1024 + 2 * PAD + L2_PAD);
+
+        let events = smme.recent_allocations();
+        assert!(events[..HISTORY_LEN - 3].iter().all(|e| e.is_none()));
+        assert_eq!(events[HISTORY_LEN - 3], Some(AllocationEvent { size: 100 + PAD, pool: PoolId::L0 }));
+        assert_eq!(events[HISTORY_LEN - 2].unwrap().pool, PoolId::L1);
+        assert_eq!(events[HISTORY_LEN - 1].unwrap().pool, PoolId::L2);
+    }
+
+    #[test]
+    fn test_allocations_since_cursor() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        smme.allocate(100).unwrap();
+        smme.allocate(300 * 1024).unwrap();
+
+        let (events, cursor) = smme.allocations_since(0);
+        assert_eq!(cursor, 2);
+        assert_eq!(events[0].unwrap().pool, PoolId::L0);
+        assert_eq!(events[1].unwrap().pool, PoolId::L1);
+        assert!(events[2..].iter().all(|e| e.is_none()));
+
+        // Nothing new since the last read
+        assert!(smme.allocations_since(cursor).0.iter().all(|e| e.is_none()));
+
+        // A reader that falls behind gets the newest HISTORY_LEN events
+        for _ in 0..HISTORY_LEN + 4 {
+            smme.free(smme.allocate(64).unwrap()).unwrap();
+        }
+        let (events, next) = smme.allocations_since(cursor);
+        assert_eq!(next, cursor + HISTORY_LEN + 4);
+        assert!(events.iter().all(|e| e.unwrap().pool == PoolId::L0));
+    }
+
+    #[test]
+    fn test_predictive_reservoir() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+
+        assert_eq!(smme.refill_reservoir(200 * 1024), RESERVOIR_SLOTS);
+        assert_eq!(smme.stats().l1_reserved, RESERVOIR_SLOTS * 200 * 1024);
+
+        // Same block size is served from the reservoir
+        let hit = smme.allocate(199 * 1024).unwrap();
+        let miss = smme.allocate(64).unwrap();
+        let stats = smme.stats().reservoir;
+        assert_eq!(stats.target, 200 * 1024);
+        assert_eq!((stats.hits, stats.misses, stats.ready), (1, 1, RESERVOIR_SLOTS - 1));
+        assert_eq!(stats.hit_rate(), 50);
+
+        // Blocks from the reservoir free like any other
+        smme.free(hit).unwrap();
+        smme.free(miss).unwrap();
+        assert_eq!(smme.refill_reservoir(200 * 1024), 1);
+
+        // A new prediction drains the old size class
+        assert_eq!(smme.refill_reservoir(8 * 1024), RESERVOIR_SLOTS);
+        let stats = smme.stats();
+        assert_eq!(stats.l1_reserved, 0);
+        // Plus the slab page `miss` came from, cached now that it is empty
+        assert_eq!(stats.l0_reserved, RESERVOIR_SLOTS * 8 * 1024 + PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_memory_map_layout() {
+        let ram = std::vec![0u8; 32 * 1024 * 1024 + POOL_ALIGN].leak();
+        let base = (ram.as_ptr() as usize).next_multiple_of(POOL_ALIGN);
+        let regions = [MemoryRegion::new(base, 32 * 1024 * 1024)];
+        let smme = SymbianModernMemoryEngine::from_memory_map(32 * 1024 * 1024, &regions).unwrap();
+
+        let small = smme.allocate(1024).unwrap().addr();
+        let large = smme.allocate(4 * 1024 * 1024).unwrap().addr();
+        assert!((base..base + 0x1_0000).contains(&small));
+        assert!(large >= base + 0x21_0000 && large + 4 * 1024 * 1024 <= base + 0x200_0000);
+
+        // Small boards get a proportionally small L2
+        let smme = SymbianModernMemoryEngine::new(5 * 1024 * 1024).unwrap();
+        assert!(smme.allocate(3 * 1024 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_default_layout_rejects_small_ram() {
+        let too_small = [L0_POOL_SIZE + L1_POOL_SIZE, 4 * 1024 * 1024, 0];
+        for total_ram in too_small {
+            assert!(matches!(
+                SymbianModernMemoryEngine::new(total_ram),
+                Err(LayoutError::InsufficientMemory)
+            ));
+        }
+        assert!(SymbianModernMemoryEngine::new(L0_POOL_SIZE + L1_POOL_SIZE + L2_POOL_MIN).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "smme-debug")]
+    fn test_redzone_tracking_has_no_fixed_cap() {
+        use crate::memory::redzone::INLINE_TRACKED;
+
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let caps: std::vec::Vec<_> = (0..INLINE_TRACKED + 100).map(|_| smme.allocate(16).unwrap()).collect();
+        assert_eq!(smme.redzones.tracked(), INLINE_TRACKED + 100);
+
+        for cap in caps {
+            smme.free(cap).unwrap();
+        }
+        assert_eq!(smme.redzones.tracked(), 0);
+        assert!(smme.check_redzones().is_none());
+    }
+
+    #[test]
+    fn test_global_realloc_in_place() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let layout = Layout::from_size_align(10, 8).unwrap();
+
+        unsafe {
+            let ptr = smme.alloc(layout);
+            // Growth within the same size class needs no copy; debug builds always move
+            let grown = smme.realloc(ptr, layout, 16);
+            assert_eq!(grown == ptr, !cfg!(feature = "smme-debug"));
+        }
+    }
+
+    #[test]
+    fn test_global_realloc_commits_growth() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let layout = Layout::from_size_align(L1_POOL_SIZE + PAGE_SIZE, 8).unwrap();
+
+        unsafe {
+            // L2 granules span several pages, so the block has room to grow into
+            let ptr = smme.alloc(layout);
+            assert!(!smme.l2_pool.is_committed(ptr as usize + L1_POOL_SIZE + 2 * PAGE_SIZE));
+
+            let new_size = L1_POOL_SIZE + 3 * PAGE_SIZE;
+            let grown = smme.realloc(ptr, layout, new_size) as usize;
+            assert_eq!(grown == ptr as usize, !cfg!(feature = "smme-debug"));
+            assert!((grown..grown + new_size)
+                .step_by(PAGE_SIZE)
+                .all(|addr| smme.l2_pool.is_committed(addr)));
+        }
+    }
+
+    #[test]
+    fn test_commit_mapped() {
+        let ram = std::vec![0u8; 8 << 20].leak();
+        let region = MemoryRegion::new(ram.as_ptr() as usize, ram.len());
+        let smme = SymbianModernMemoryEngine::from_memory_map(8 << 20, &[region]).unwrap();
+
+        let mut frames = &smme;
+        let mut space = AddressSpace::new(&mut frames, 3).unwrap();
+        let cap = smme.allocate_aligned_for(3, 4 * PAGE_SIZE, PAGE_SIZE).unwrap();
+
+        smme.commit_mapped(3, &cap, 100, 2 * PAGE_SIZE, &mut space, &mut frames).unwrap();
+        let user_rw = Access::READ_WRITE.union(Access::USER);
+        assert_eq!(space.translate(&mut frames, cap.addr() + 8), Some((cap.addr() + 8, user_rw)));
+        assert!(space.translate(&mut frames, cap.addr() + 2 * PAGE_SIZE).is_some());
+        assert_eq!(space.translate(&mut frames, cap.addr() + 3 * PAGE_SIZE), None);
+
+        // Read-only holders can't change what is mapped
+        let read_only = smme.restrict(&cap, Rights::READ).unwrap();
+        assert!(smme.commit_mapped(3, &read_only, 0, PAGE_SIZE, &mut space, &mut frames).is_err());
+
+        // A slab object's page holds other owners' objects, so it never reaches user space
+        let small = smme.allocate_for(3, 64).unwrap();
+        assert!(matches!(
+            smme.commit_mapped(3, &small, 0, 64, &mut space, &mut frames),
+            Err(AllocationError::AccessDenied)
+        ));
+        assert_eq!(space.translate(&mut frames, small.addr()), None);
+        smme.free_for(3, small).unwrap();
+
+        smme.decommit_unmapped(3, &cap, PAGE_SIZE, PAGE_SIZE, &mut space, &mut frames).unwrap();
+        assert_eq!(space.translate(&mut frames, cap.addr() + PAGE_SIZE), None);
+        assert!(space.translate(&mut frames, cap.addr()).is_some());
+
+        let tables = smme.stats().total_reserved;
+        space.destroy(&mut frames);
+        assert!(smme.stats().total_reserved < tables);
     }
 }
@@ -0,0 +1,148 @@
+//! Memory statistics exported to monitoring agents through the C API
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Buckets of the per-pool request size histogram
+pub const SIZE_BUCKETS: usize = 24;
+/// Upper bound of the first histogram bucket; each following bucket doubles it
+pub const SMALLEST_BUCKET: usize = 16;
+/// Bumped whenever `MemoryStatsSnapshot` changes layout
+pub const STATS_VERSION: u32 = 1;
+
+/// Histogram bucket for a request: bucket `i` holds sizes up to `16 << i`,
+/// the last one everything larger
+pub fn bucket_for(size: usize) -> usize {
+    size.max(SMALLEST_BUCKET)
+        .checked_next_power_of_two()
+        .map_or(SIZE_BUCKETS - 1, |bound| {
+            ((bound.trailing_zeros() - SMALLEST_BUCKET.trailing_zeros()) as usize).min(SIZE_BUCKETS - 1)
+        })
+}
+
+/// Running counters SMME keeps for each pool
+pub struct PoolCounters {
+    allocations: AtomicU64,
+    frees: AtomicU64,
+    failures: AtomicU64,
+    histogram: [AtomicU64; SIZE_BUCKETS],
+}
+
+impl PoolCounters {
+    pub const fn new() -> Self {
+        Self {
+            allocations: AtomicU64::new(0),
+            frees: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            histogram: [const { AtomicU64::new(0) }; SIZE_BUCKETS],
+        }
+    }
+
+    pub fn record_allocation(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.histogram[bucket_for(size)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_free(&self) {
+        self.frees.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The pool could not satisfy a request on its own
+    pub fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Fill the counter fields of `stats`
+    pub fn export(&self, stats: &mut PoolStats) {
+        stats.allocations = self.allocations.load(Ordering::Relaxed);
+        stats.frees = self.frees.load(Ordering::Relaxed);
+        stats.failures = self.failures();
+        for (out, count) in stats.histogram.iter_mut().zip(&self.histogram) {
+            *out = count.load(Ordering::Relaxed);
+        }
+    }
+}
+
+/// One pool as seen by the monitoring agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct PoolStats {
+    pub base: usize,
+    pub size: usize,
+    pub reserved: usize,
+    pub committed: usize,
+    /// Address space not reserved by anyone
+    pub free: usize,
+    /// Highest `committed` seen since boot
+    pub peak_committed: usize,
+    pub allocations: u64,
+    pub frees: u64,
+    pub failures: u64,
+    /// Requests by size; see `bucket_for`
+    pub histogram: [u64; SIZE_BUCKETS],
+}
+
+impl PoolStats {
+    pub const fn empty() -> Self {
+        Self {
+            base: 0,
+            size: 0,
+            reserved: 0,
+            committed: 0,
+            free: 0,
+            peak_committed: 0,
+            allocations: 0,
+            frees: 0,
+            failures: 0,
+            histogram: [0; SIZE_BUCKETS],
+        }
+    }
+}
+
+/// Plain-data copy of the SMME statistics, returned by `aether_get_memory_stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct MemoryStatsSnapshot {
+    /// `STATS_VERSION` of the producer
+    pub version: u32,
+    /// Indexed by `PoolId`
+    pub pools: [PoolStats; 3],
+    pub total_reserved: usize,
+    pub total_committed: usize,
+    pub spills: usize,
+    pub remote_allocations: usize,
+    pub remote_bytes: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        assert_eq!(bucket_for(0), 0);
+        assert_eq!(bucket_for(16), 0);
+        assert_eq!(bucket_for(17), 1);
+        assert_eq!(bucket_for(4096), 8);
+        assert_eq!(bucket_for(64 << 20), SIZE_BUCKETS - 2);
+        assert_eq!(bucket_for(usize::MAX), SIZE_BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_counters_export() {
+        let counters = PoolCounters::new();
+        counters.record_allocation(24);
+        counters.record_allocation(32);
+        counters.record_allocation(100);
+        counters.record_free();
+        counters.record_failure();
+
+        let mut stats = PoolStats::empty();
+        counters.export(&mut stats);
+        assert_eq!((stats.allocations, stats.frees, stats.failures), (3, 1, 1));
+        assert_eq!(&stats.histogram[..4], &[0, 2, 0, 1]);
+    }
+}
@@ -3,11 +3,15 @@
 
 #![no_std]
 
+use crate::memory::smme::{AllocationEvent, SymbianModernMemoryEngine};
+
 /// Simple decision tree for memory prediction
 pub struct TinyMLPredictor {
     // Historical allocation sizes
     history: [usize; 16],
     history_index: usize,
+    // SMME allocation history already learned from
+    seen: usize,
 }
 
 impl TinyMLPredictor {
@@ -15,6 +19,7 @@ impl TinyMLPredictor {
         Self {
             history: [0; 16],
             history_index: 0,
+            seen: 0,
         }
     }
 
@@ -24,6 +29,21 @@ impl TinyMLPredictor {
         self.history_index = (self.history_index + 1) % 16;
     }
 
+    /// Record an allocation event reported by SMME
+    pub fn record_event(&mut self, event: AllocationEvent) {
+        self.record_allocation(event.size);
+    }
+
+    /// Learn from the allocations SMME recorded since the last call.
+    /// Runs from `kernel_tick`, never from inside the allocator.
+    pub fn observe(&mut self, smme: &SymbianModernMemoryEngine) {
+        let (events, next) = smme.allocations_since(self.seen);
+        self.seen = next;
+        for event in events.into_iter().flatten() {
+            self.record_event(event);
+        }
+    }
+
     /// Predict next allocation size
     pub fn predict_next_size(&self) -> usize {
         // Simple moving average
@@ -122,6 +142,22 @@ mod tests {
         assert!(predicted > 1000 && predicted < 3000);
     }
 
+    #[test]
+    fn test_observe_smme_history() {
+        let smme = SymbianModernMemoryEngine::host_backed();
+        let mut predictor = TinyMLPredictor::new();
+
+        smme.allocate(256 * 1024).unwrap();
+        smme.allocate(256 * 1024).unwrap();
+        predictor.observe(&smme);
+        let predicted = predictor.predict_next_size();
+        assert!((256 * 1024..257 * 1024).contains(&predicted));
+
+        // Events are only learned once
+        predictor.observe(&smme);
+        assert_eq!(predictor.predict_next_size(), predicted);
+    }
+
     #[test]
     fn test_distribution_decision() {
         let predictor = TinyMLPredictor::new();
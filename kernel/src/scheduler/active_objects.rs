@@ -1,13 +1,26 @@
 //! Active Objects Scheduler - Symbian DNA
-//! Cooperative multitasking with message passing
+//! Cooperative multitasking with message passing and asynchronous requests
 
 #![no_std]
 
 use core::sync::atomic::{AtomicU32, Ordering};
 
+use crate::memory::smme::{MemoryPressure, PoolId};
+
+use super::ready_queue::ReadyQueues;
+use super::request::{RequestHandle, RequestKind, RequestSet, STATUS_OK};
+use super::timer::TimerQueue;
+
 const MAX_OBJECTS: usize = 256;
+/// Low bits of an object id select its slot, the rest count how often the slot was reused
+const SLOT_BITS: u32 = 16;
 const MAX_MESSAGES: usize = 16;
 
+/// System message carrying a `MemoryPressure` level in `data`
+pub const MSG_MEMORY_PRESSURE: u32 = 0xFFFF_0001;
+/// System message carrying the `PoolId` that overflowed into a larger pool in `data`
+pub const MSG_POOL_SPILLED: u32 = 0xFFFF_0002;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ObjectState {
     Idle,
@@ -29,13 +42,120 @@ impl Message {
     }
 }
 
+/// Behaviour of an active object (Symbian `RunL`), run once per dispatched message
+/// or completed request
+pub trait MessageHandler {
+    fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext);
+
+    /// A request issued by this object finished with `status` (`STATUS_OK` or a negative error)
+    fn request_complete(&mut self, _request: RequestHandle, _status: i32, _ctx: &mut SchedulerContext) {}
+
+    /// Symbian `DoCancel`: abandon whatever the object was waiting for
+    fn do_cancel(&mut self) {}
+}
+
+/// Slot an object id lives in; the ids of later objects in the same slot differ in the high bits
+pub fn object_slot(id: u32) -> usize {
+    (id & ((1 << SLOT_BITS) - 1)) as usize
+}
+
+/// What a handler may do to the scheduler while it runs
+pub struct SchedulerContext<'a> {
+    scheduler: &'a mut ActiveObjectScheduler,
+    current: u32,
+    finished: bool,
+    // The handler destroyed its own object; applied once it returns
+    destroyed: bool,
+}
+
+impl SchedulerContext<'_> {
+    /// Id of the object being run
+    pub fn id(&self) -> u32 {
+        self.current
+    }
+
+    pub fn send(&mut self, to: u32, msg: Message) -> Result<(), ()> {
+        self.scheduler.send_message(to, msg)
+    }
+
+    pub fn spawn(&mut self, priority: u8, handler: &'static mut dyn MessageHandler) -> Result<u32, ()> {
+        self.scheduler.create_object(priority, handler)
+    }
+
+    /// Destroy an object, e.g. a worker this one spawned. Destroying the running
+    /// object takes effect when its handler returns, so `do_cancel` still runs.
+    pub fn destroy(&mut self, id: u32) -> Result<(), ()> {
+        if id != self.current {
+            return self.scheduler.destroy_object(id);
+        }
+        if self.destroyed {
+            return Err(());
+        }
+        self.destroyed = true;
+        Ok(())
+    }
+
+    /// Start an asynchronous request; the object waits until a provider completes it
+    pub fn issue(&mut self, kind: RequestKind) -> Result<RequestHandle, ()> {
+        self.scheduler.issue_request(self.current, kind)
+    }
+
+    pub fn cancel_request(&mut self, request: RequestHandle) -> Result<(), ()> {
+        self.scheduler.cancel_request(request)
+    }
+
+    /// Wait `ticks` kernel ticks; the timer provider completes the request with `STATUS_OK`
+    pub fn after(&mut self, ticks: u64) -> Result<RequestHandle, ()> {
+        self.scheduler.start_timer(self.current, ticks)
+    }
+
+    /// Stop the running object once the handler returns; queued messages are dropped
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+/// How long an object sat ready before running, counted in dispatches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitStats {
+    pub dispatches: u64,
+    pub total_wait: u64,
+    pub max_wait: u64,
+    /// Time spent ready so far; zero unless the object is queued
+    pub current_wait: u64,
+    /// Priority after aging
+    pub effective_priority: u8,
+}
+
+impl WaitStats {
+    pub const fn empty() -> Self {
+        Self {
+            dispatches: 0,
+            total_wait: 0,
+            max_wait: 0,
+            current_wait: 0,
+            effective_priority: 0,
+        }
+    }
+}
+
 pub struct ActiveObject {
     id: u32,
     priority: u8,
+    // Raised by aging while the object waits, back to `priority` once it runs
+    effective_priority: u8,
+    // Dispatch count when the object was last queued
+    ready_since: u64,
+    wait: WaitStats,
     state: ObjectState,
     mailbox: [Message; MAX_MESSAGES],
     mailbox_head: usize,
     mailbox_tail: usize,
+    // Receives MSG_MEMORY_PRESSURE and MSG_POOL_SPILLED
+    pressure_subscriber: bool,
+    // Taken out while the object runs
+    handler: Option<&'static mut dyn MessageHandler>,
+    requests: RequestSet,
 }
 
 impl ActiveObject {
@@ -43,41 +163,24 @@ impl ActiveObject {
         Self {
             id,
             priority,
+            effective_priority: priority,
+            ready_since: 0,
+            wait: WaitStats::empty(),
             state: ObjectState::Idle,
             mailbox: [Message::empty(); MAX_MESSAGES],
             mailbox_head: 0,
             mailbox_tail: 0,
+            pressure_subscriber: false,
+            handler: None,
+            requests: RequestSet::new(),
         }
     }
 
     pub fn post_message(&mut self, msg: Message) -> Result<(), ()> {
-        let next_tail = (self.mailbox_tail + 1) % MAX_MESSAGES;
-        if next_tail == self.mailbox_head {
-            return Err(()); // Mailbox full
-        }
-        
-        self.mailbox[self.mailbox_tail] = msg;
-        self.mailbox_tail = next_tail;
-        
-        if self.state == ObjectState::Idle {
-            self.state = ObjectState::Ready;
-        }
-        
-        Ok(())
-    }
-
-    pub fn get_message(&mut self) -> Option<Message> {
-        if self.mailbox_head == self.mailbox_tail {
-            return None;
+        if self.state == ObjectState::Finished {
+            return Err(());
         }
-        
-        let msg = self.mailbox[self.mailbox_head];
-        self.mailbox_head = (self.mailbox_head + 1) % MAX_MESSAGES;
-        msg
-        Ok(())
-    }
 
-    pub fn post_message(&mut self, msg: Message) -> Result<(), ()> {
         let next_tail = (self.mailbox_tail + 1) % MAX_MESSAGES;
         if next_tail == self.mailbox_head {
             return Err(()); // Mailbox full
@@ -86,13 +189,17 @@ impl ActiveObject {
         self.mailbox[self.mailbox_tail] = msg;
         self.mailbox_tail = next_tail;
         
-        if self.state == ObjectState::Idle {
+        if matches!(self.state, ObjectState::Idle | ObjectState::Waiting) {
             self.state = ObjectState::Ready;
         }
         
         Ok(())
     }
 
+    pub fn pending_messages(&self) -> usize {
+        (self.mailbox_tail + MAX_MESSAGES - self.mailbox_head) % MAX_MESSAGES
+    }
+
     pub fn get_message(&mut self) -> Option<Message> {
         if self.mailbox_head == self.mailbox_tail {
             return None;
@@ -107,8 +214,21 @@ impl ActiveObject {
 
 pub struct ActiveObjectScheduler {
     objects: [Option<ActiveObject>; MAX_OBJECTS],
+    // Last object dispatched
     current_object: AtomicU32,
     object_count: usize,
+    // Slots below this have been handed out at least once
+    next_slot: usize,
+    free_slots: [u16; MAX_OBJECTS],
+    free_count: usize,
+    generations: [u16; MAX_OBJECTS],
+    ready: ReadyQueues<MAX_OBJECTS>,
+    next_request: u32,
+    timers: TimerQueue,
+    dispatches: u64,
+    // Dispatches a ready object waits per priority level it gains; zero disables aging
+    aging_interval: u64,
+    promotions: u64,
 }
 
 impl ActiveObjectScheduler {
@@ -118,64 +238,369 @@ impl ActiveObjectScheduler {
             objects: [NONE; MAX_OBJECTS],
             current_object: AtomicU32::new(0),
             object_count: 0,
+            next_slot: 0,
+            free_slots: [0; MAX_OBJECTS],
+            free_count: 0,
+            generations: [0; MAX_OBJECTS],
+            ready: ReadyQueues::new(),
+            next_request: 0,
+            timers: TimerQueue::new(),
+            dispatches: 0,
+            aging_interval: 0,
+            promotions: 0,
         }
     }
 
-    pub fn create_object(&mut self, priority: u8) -> Result<u32, ()> {
-        if self.object_count >= MAX_OBJECTS {
+    /// Let waiting objects gain one priority level every `interval` dispatches,
+    /// so a flood of high priority work can't starve them. Zero turns aging off.
+    pub fn set_aging_interval(&mut self, interval: u32) {
+        self.aging_interval = interval as u64;
+    }
+
+    pub fn create_object(
+        &mut self,
+        priority: u8,
+        handler: &'static mut dyn MessageHandler,
+    ) -> Result<u32, ()> {
+        // Recycle destroyed slots before touching fresh ones
+        let slot = if self.free_count > 0 {
+            self.free_count -= 1;
+            self.free_slots[self.free_count] as usize
+        } else if self.next_slot < MAX_OBJECTS {
+            self.next_slot += 1;
+            self.next_slot - 1
+        } else {
             return Err(());
-        }
+        };
         
-        let id = self.object_count as u32;
-        self.objects[self.object_count] = Some(ActiveObject::new(id, priority));
+        let id = ((self.generations[slot] as u32) << SLOT_BITS) | slot as u32;
+        let mut object = ActiveObject::new(id, priority);
+        object.handler = Some(handler);
+        self.objects[slot] = Some(object);
         self.object_count += 1;
         
         Ok(id)
     }
 
+    /// Symbian-style `Cancel`: run the handler's `do_cancel` and drop the object's
+    /// queued messages and outstanding requests. Returns how many messages were dropped.
+    pub fn cancel(&mut self, id: u32) -> Result<usize, ()> {
+        let slot = self.slot(id).ok_or(())?;
+        let Some(obj) = self.objects[slot].as_mut() else {
+            return Err(());
+        };
+
+        if let Some(handler) = obj.handler.as_mut() {
+            handler.do_cancel();
+        }
+        let dropped = obj.pending_messages();
+        obj.mailbox_head = obj.mailbox_tail;
+        obj.requests.cancel_all();
+        if matches!(obj.state, ObjectState::Ready | ObjectState::Waiting) {
+            obj.state = ObjectState::Idle;
+        }
+        self.ready.remove(slot);
+        self.timers.disarm_object(id);
+        Ok(dropped)
+    }
+
+    /// Cancel and remove an object. Its id goes stale and the slot is reused by
+    /// later objects under a new id.
+    pub fn destroy_object(&mut self, id: u32) -> Result<(), ()> {
+        self.cancel(id)?;
+        let slot = object_slot(id);
+        self.objects[slot] = None;
+        self.generations[slot] = self.generations[slot].wrapping_add(1);
+        self.free_slots[self.free_count] = slot as u16;
+        self.free_count += 1;
+        self.object_count -= 1;
+        Ok(())
+    }
+
+    /// Issue an asynchronous request on behalf of `id`, which waits for it once
+    /// it has nothing else to run
+    pub fn issue_request(&mut self, id: u32, kind: RequestKind) -> Result<RequestHandle, ()> {
+        let slot = self.slot(id).ok_or(())?;
+        let Some(obj) = self.objects[slot].as_mut() else {
+            return Err(());
+        };
+        if obj.state == ObjectState::Finished {
+            return Err(());
+        }
+
+        let seq = self.next_request;
+        obj.requests.issue(seq, kind)?;
+        self.next_request = seq.wrapping_add(1);
+        if obj.state == ObjectState::Idle {
+            obj.state = ObjectState::Waiting;
+        }
+        Ok(RequestHandle::new(id, seq))
+    }
+
+    /// Signal completion of a request (Symbian `RequestComplete`). Completions run
+    /// in the order they are signalled; each request completes at most once.
+    pub fn complete_request(&mut self, request: RequestHandle, status: i32) -> Result<(), ()> {
+        let slot = self.slot(request.object()).ok_or(())?;
+        let Some(obj) = self.objects[slot].as_mut() else {
+            return Err(());
+        };
+
+        obj.requests.complete(request.seq(), status)?;
+        if matches!(obj.state, ObjectState::Idle | ObjectState::Waiting) {
+            obj.state = ObjectState::Ready;
+            Self::enqueue(&mut self.ready, slot, obj, self.dispatches);
+        }
+        Ok(())
+    }
+
+    /// Withdraw a request that hasn't completed yet; the handler never hears of it
+    pub fn cancel_request(&mut self, request: RequestHandle) -> Result<(), ()> {
+        let slot = self.slot(request.object()).ok_or(())?;
+        let Some(obj) = self.objects[slot].as_mut() else {
+            return Err(());
+        };
+
+        obj.requests.cancel(request.seq())?;
+        if obj.state == ObjectState::Waiting && obj.requests.outstanding_count() == 0 {
+            obj.state = ObjectState::Idle;
+        }
+        self.timers.disarm(request);
+        Ok(())
+    }
+
+    /// Issue a timer request on behalf of `id` that completes `ticks` ticks from now
+    pub fn start_timer(&mut self, id: u32, ticks: u64) -> Result<RequestHandle, ()> {
+        let request = self.issue_request(id, RequestKind::Timer)?;
+        if self.timers.arm(request, ticks).is_err() {
+            let _ = self.cancel_request(request);
+            return Err(());
+        }
+        Ok(request)
+    }
+
+    /// Timer provider: advance one kernel tick and complete the timers that ran out.
+    /// Returns how many requests completed.
+    pub fn tick(&mut self) -> usize {
+        self.timers.advance();
+        let mut completed = 0;
+        while let Some(request) = self.timers.expired() {
+            if self.complete_request(request, STATUS_OK).is_ok() {
+                completed += 1;
+            }
+        }
+        completed
+    }
+
+    /// What an outstanding request is waiting for
+    pub fn request_kind(&self, request: RequestHandle) -> Option<RequestKind> {
+        let slot = self.slot(request.object())?;
+        self.objects[slot].as_ref()?.requests.kind(request.seq())
+    }
+
+    /// Slot of a live object, rejecting ids whose slot has been reused since
+    fn slot(&self, id: u32) -> Option<usize> {
+        let slot = object_slot(id);
+        match self.objects.get(slot) {
+            Some(Some(obj)) if obj.id == id => Some(slot),
+            _ => None,
+        }
+    }
+
     pub fn send_message(&mut self, to: u32, msg: Message) -> Result<(), ()> {
-        if let Some(Some(obj)) = self.objects.get_mut(to as usize) {
-            obj.post_message(msg)
-        } else {
-            Err(())
+        let idx = self.slot(to).ok_or(())?;
+        self.post(idx, msg)
+    }
+
+    /// Deliver `msg`, queueing the object if it just became ready
+    fn post(&mut self, idx: usize, msg: Message) -> Result<(), ()> {
+        let Some(obj) = self.objects[idx].as_mut() else {
+            return Err(());
+        };
+        obj.post_message(msg)?;
+        if obj.state == ObjectState::Ready && !self.ready.is_queued(idx) {
+            Self::enqueue(&mut self.ready, idx, obj, self.dispatches);
+        }
+        Ok(())
+    }
+
+    fn enqueue(ready: &mut ReadyQueues<MAX_OBJECTS>, idx: usize, obj: &mut ActiveObject, now: u64) {
+        obj.effective_priority = obj.priority;
+        obj.ready_since = now;
+        ready.push(idx, obj.priority);
+    }
+
+    /// Promote queued objects by how long they have been waiting
+    fn age_waiting(&mut self) {
+        let interval = self.aging_interval;
+        if interval == 0 || !self.dispatches.is_multiple_of(interval) {
+            return;
+        }
+
+        for idx in 0..self.next_slot {
+            if !self.ready.is_queued(idx) {
+                continue;
+            }
+            let Some(obj) = self.objects[idx].as_mut() else {
+                continue;
+            };
+            let waited = self.dispatches - obj.ready_since;
+            let aged = (obj.priority as u64 + waited / interval).min(u8::MAX as u64) as u8;
+            if aged > obj.effective_priority {
+                obj.effective_priority = aged;
+                self.ready.remove(idx);
+                self.ready.push(idx, aged);
+                self.promotions += 1;
+            }
+        }
+    }
+
+    /// Ask for MSG_MEMORY_PRESSURE and MSG_POOL_SPILLED notifications
+    pub fn subscribe_memory_pressure(&mut self, id: u32) -> Result<(), ()> {
+        self.set_pressure_subscriber(id, true)
+    }
+
+    pub fn unsubscribe_memory_pressure(&mut self, id: u32) -> Result<(), ()> {
+        self.set_pressure_subscriber(id, false)
+    }
+
+    fn set_pressure_subscriber(&mut self, id: u32, subscribed: bool) -> Result<(), ()> {
+        let slot = self.slot(id).ok_or(())?;
+        match self.objects[slot].as_mut() {
+            Some(obj) => {
+                obj.pressure_subscriber = subscribed;
+                Ok(())
+            }
+            None => Err(()),
         }
     }
 
-    /// Cooperative scheduling - Symbian style
+    /// Deliver a memory pressure change to every subscriber.
+    /// Returns how many objects received it; full mailboxes miss the event.
+    pub fn notify_memory_pressure(&mut self, level: MemoryPressure) -> usize {
+        self.notify_subscribers(Message {
+            id: MSG_MEMORY_PRESSURE,
+            data: level as u64,
+        })
+    }
+
+    /// Tell every subscriber that `pool` ran out and a request spilled past it
+    pub fn notify_pool_spilled(&mut self, pool: PoolId) -> usize {
+        self.notify_subscribers(Message {
+            id: MSG_POOL_SPILLED,
+            data: pool as u64,
+        })
+    }
+
+    fn notify_subscribers(&mut self, msg: Message) -> usize {
+        let mut delivered = 0;
+        for idx in 0..self.next_slot {
+            let subscribed = self.objects[idx]
+                .as_ref()
+                .is_some_and(|obj| obj.pressure_subscriber);
+            if subscribed && self.post(idx, msg).is_ok() {
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    /// Cooperative scheduling - Symbian style.
+    /// Runs one message of the highest priority ready object; equal priorities take turns.
+    /// Selection is O(1); with aging on, every `aging_interval` dispatches also walk the objects.
     pub fn schedule(&mut self) {
-        let mut scheduled = 0;
-        
-        // Round-robin with priority
-        for _ in 0..MAX_OBJECTS {
-            let idx = self.current_object.load(Ordering::Relaxed) as usize;
-            
-            if let Some(Some(obj)) = self.objects.get_mut(idx) {
-                if obj.state == ObjectState::Ready {
-                    obj.state = ObjectState::Running;
-                    
-                    // Process one message
-                    if let Some(msg) = obj.get_message() {
-                        // In real impl: Execute object's handler
-                        // For now: Just mark as processed
-                        scheduled += 1;
-                    }
-                    
-                    // Check if more messages
-                    if obj.mailbox_head == obj.mailbox_tail {
-                        obj.state = ObjectState::Idle;
-                    } else {
-                        obj.state = ObjectState::Ready;
-                    }
-                    
-                    break;
+        self.age_waiting();
+        let Some(idx) = self.ready.pop() else {
+            return;
+        };
+        let Some(obj) = self.objects[idx].as_mut() else {
+            return;
+        };
+        let id = obj.id;
+        self.current_object.store(id, Ordering::Relaxed);
+        let waited = self.dispatches - obj.ready_since;
+        obj.wait.dispatches += 1;
+        obj.wait.total_wait += waited;
+        obj.wait.max_wait = obj.wait.max_wait.max(waited);
+        obj.effective_priority = obj.priority;
+        obj.state = ObjectState::Running;
+        // Completed requests go before new messages
+        let event = match obj.requests.next_completion() {
+            Some((seq, status)) => Some(Event::Completion(RequestHandle::new(id, seq), status)),
+            None => obj.get_message().map(Event::Message),
+        };
+
+        self.dispatches += 1;
+        if let Some(event) = event {
+            self.dispatch(idx, event);
+        }
+
+        // Back of its priority queue if more messages are waiting.
+        // The handler may have destroyed the object and its slot been reused.
+        if let Some(obj) = self.objects[idx].as_mut().filter(|obj| obj.id == id) {
+            if obj.state == ObjectState::Running {
+                if obj.requests.has_completions() || obj.pending_messages() > 0 {
+                    obj.state = ObjectState::Ready;
+                    Self::enqueue(&mut self.ready, idx, obj, self.dispatches);
+                } else if obj.requests.outstanding_count() > 0 {
+                    obj.state = ObjectState::Waiting;
+                } else {
+                    obj.state = ObjectState::Idle;
                 }
             }
-            
-            // Move to next object
-            self.current_object.store(
-                ((idx + 1) % self.object_count) as u32,
-                Ordering::Relaxed
-            );
+        }
+    }
+
+    /// Run the object's handler on `event`
+    fn dispatch(&mut self, idx: usize, event: Event) {
+        let Some(obj) = self.objects[idx].as_mut() else {
+            return;
+        };
+        let id = obj.id;
+        let Some(handler) = obj.handler.take() else {
+            return;
+        };
+
+        let mut ctx = SchedulerContext {
+            scheduler: self,
+            current: id,
+            finished: false,
+            destroyed: false,
+        };
+        match event {
+            Event::Message(msg) => handler.handle(msg, &mut ctx),
+            Event::Completion(request, status) => handler.request_complete(request, status, &mut ctx),
+        }
+        let (finished, destroyed) = (ctx.finished, ctx.destroyed);
+
+        if let Some(obj) = self.objects[idx].as_mut().filter(|obj| obj.id == id) {
+            obj.handler = Some(handler);
+            if destroyed {
+                let _ = self.destroy_object(id);
+            } else if finished {
+                obj.state = ObjectState::Finished;
+                obj.mailbox_head = obj.mailbox_tail;
+                obj.requests.cancel_all();
+            }
+        }
+    }
+
+    /// Wait statistics of a live object
+    pub fn wait_stats(&self, id: u32) -> Option<WaitStats> {
+        self.slot(id).map(|idx| self.object_wait(idx))
+    }
+
+    fn object_wait(&self, idx: usize) -> WaitStats {
+        let Some(obj) = &self.objects[idx] else {
+            return WaitStats::empty();
+        };
+        WaitStats {
+            current_wait: if self.ready.is_queued(idx) {
+                self.dispatches - obj.ready_since
+            } else {
+                0
+            },
+            effective_priority: obj.effective_priority,
+            ..obj.wait
         }
     }
 
@@ -183,13 +608,23 @@ impl ActiveObjectScheduler {
         let mut idle = 0;
         let mut ready = 0;
         let mut running = 0;
+        let mut waiting = 0;
+        let mut finished = 0;
+        let mut max_wait = 0;
         
-        for obj in self.objects.iter().flatten() {
+        for (idx, obj) in self.objects.iter().enumerate() {
+            let Some(obj) = obj else {
+                continue;
+            };
+            let wait = self.object_wait(idx);
+            max_wait = max_wait.max(wait.max_wait).max(wait.current_wait);
+
             match obj.state {
                 ObjectState::Idle => idle += 1,
                 ObjectState::Ready => ready += 1,
                 ObjectState::Running => running += 1,
-                _ => {}
+                ObjectState::Waiting => waiting += 1,
+                ObjectState::Finished => finished += 1,
             }
         }
         
@@ -198,33 +633,76 @@ impl ActiveObjectScheduler {
             idle_objects: idle,
             ready_objects: ready,
             running_objects: running,
+            waiting_objects: waiting,
+            finished_objects: finished,
+            dispatches: self.dispatches,
+            promotions: self.promotions,
+            max_wait,
         }
     }
 }
 
+/// One unit of work for a handler
+enum Event {
+    Message(Message),
+    Completion(RequestHandle, i32),
+}
+
 #[derive(Debug)]
 pub struct SchedulerStats {
     pub total_objects: usize,
     pub idle_objects: usize,
     pub ready_objects: usize,
     pub running_objects: usize,
+    /// Blocked on outstanding requests
+    pub waiting_objects: usize,
+    pub finished_objects: usize,
+    pub dispatches: u64,
+    /// Priority levels granted by aging
+    pub promotions: u64,
+    /// Longest any object has waited, including objects still waiting; see
+    /// `ActiveObjectScheduler::wait_stats` for a single object
+    pub max_wait: u64,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::sync::atomic::AtomicU64;
+    use std::boxed::Box;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    struct Noop;
+
+    impl MessageHandler for Noop {
+        fn handle(&mut self, _msg: Message, _ctx: &mut SchedulerContext) {}
+    }
+
+    fn noop() -> &'static mut dyn MessageHandler {
+        Box::leak(Box::new(Noop))
+    }
+
+    /// Adds up the data of every message it gets
+    struct Sink(&'static AtomicU64);
+
+    impl MessageHandler for Sink {
+        fn handle(&mut self, msg: Message, _ctx: &mut SchedulerContext) {
+            self.0.fetch_add(msg.data, Ordering::Relaxed);
+        }
+    }
 
     #[test]
     fn test_create_object() {
         let mut scheduler = ActiveObjectScheduler::new();
-        let id = scheduler.create_object(10).unwrap();
+        let id = scheduler.create_object(10, noop()).unwrap();
         assert_eq!(id, 0);
     }
 
     #[test]
     fn test_message_passing() {
         let mut scheduler = ActiveObjectScheduler::new();
-        let id = scheduler.create_object(10).unwrap();
+        let id = scheduler.create_object(10, noop()).unwrap();
         
         let msg = Message { id: 1, data: 42 };
         scheduler.send_message(id, msg).unwrap();
@@ -236,8 +714,8 @@ mod tests {
     #[test]
     fn test_scheduling() {
         let mut scheduler = ActiveObjectScheduler::new();
-        let id1 = scheduler.create_object(10).unwrap();
-        let id2 = scheduler.create_object(5).unwrap();
+        let id1 = scheduler.create_object(10, noop()).unwrap();
+        let id2 = scheduler.create_object(5, noop()).unwrap();
         
         scheduler.send_message(id1, Message { id: 1, data: 100 }).unwrap();
         scheduler.send_message(id2, Message { id: 2, data: 200 }).unwrap();
@@ -247,4 +725,498 @@ mod tests {
         let stats = scheduler.stats();
         assert!(stats.idle_objects + stats.ready_objects == 2);
     }
+
+    #[test]
+    fn test_memory_pressure_delivery() {
+        let mut scheduler = ActiveObjectScheduler::new();
+        let app = scheduler.create_object(5, noop()).unwrap();
+        let other = scheduler.create_object(5, noop()).unwrap();
+        scheduler.subscribe_memory_pressure(app).unwrap();
+
+        assert_eq!(scheduler.notify_memory_pressure(MemoryPressure::Warning), 1);
+
+        let obj = scheduler.objects[app as usize].as_mut().unwrap();
+        let msg = obj.get_message().unwrap();
+        assert_eq!(msg.id, MSG_MEMORY_PRESSURE);
+        assert_eq!(msg.data, MemoryPressure::Warning as u64);
+        assert!(scheduler.objects[other as usize].as_mut().unwrap().get_message().is_none());
+
+        // Spills come under their own id and leave the pressure level alone
+        assert_eq!(scheduler.notify_pool_spilled(PoolId::L0), 1);
+        let obj = scheduler.objects[app as usize].as_mut().unwrap();
+        let msg = obj.get_message().unwrap();
+        assert_eq!((msg.id, msg.data), (MSG_POOL_SPILLED, PoolId::L0 as u64));
+
+        scheduler.unsubscribe_memory_pressure(app).unwrap();
+        assert_eq!(scheduler.notify_memory_pressure(MemoryPressure::Normal), 0);
+        assert_eq!(scheduler.notify_pool_spilled(PoolId::L1), 0);
+    }
+
+    #[test]
+    fn test_handler_sends_messages() {
+        /// Passes every message on to `to` with its data doubled
+        struct Forward {
+            to: u32,
+        }
+
+        impl MessageHandler for Forward {
+            fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+                ctx.send(self.to, Message { id: msg.id, data: msg.data * 2 }).unwrap();
+            }
+        }
+
+        static TOTAL: AtomicU64 = AtomicU64::new(0);
+        let mut scheduler = ActiveObjectScheduler::new();
+        let sink = scheduler.create_object(5, Box::leak(Box::new(Sink(&TOTAL)))).unwrap();
+        let forward = scheduler
+            .create_object(5, Box::leak(Box::new(Forward { to: sink })))
+            .unwrap();
+
+        scheduler.send_message(forward, Message { id: 1, data: 21 }).unwrap();
+        scheduler.schedule();
+        assert_eq!(TOTAL.load(Ordering::Relaxed), 0);
+        scheduler.schedule();
+        assert_eq!(TOTAL.load(Ordering::Relaxed), 42);
+        assert_eq!(scheduler.stats().idle_objects, 2);
+    }
+
+    #[test]
+    fn test_handler_spawns_and_finishes() {
+        /// Message 1 starts a worker and hands it the data; message 2 ends the parent
+        struct Parent;
+
+        impl MessageHandler for Parent {
+            fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+                static TOTAL: AtomicU64 = AtomicU64::new(0);
+                match msg.id {
+                    1 => {
+                        let child = ctx.spawn(3, Box::leak(Box::new(Sink(&TOTAL)))).unwrap();
+                        ctx.send(child, msg).unwrap();
+                        ctx.send(ctx.id(), Message { id: 2, data: 0 }).unwrap();
+                    }
+                    _ => ctx.finish(),
+                }
+            }
+        }
+
+        let mut scheduler = ActiveObjectScheduler::new();
+        let parent = scheduler.create_object(5, Box::leak(Box::new(Parent))).unwrap();
+        scheduler.send_message(parent, Message { id: 1, data: 7 }).unwrap();
+
+        scheduler.schedule();
+        assert_eq!(scheduler.stats().total_objects, 2);
+        assert_eq!(scheduler.stats().ready_objects, 2);
+
+        scheduler.schedule();
+        let stats = scheduler.stats();
+        assert_eq!((stats.finished_objects, stats.ready_objects), (1, 1));
+        assert!(scheduler.send_message(parent, Message { id: 1, data: 1 }).is_err());
+    }
+
+    /// Logs `(tag, msg.data)` for every message; message id 1 also wakes `wake`
+    struct Recorder {
+        tag: u32,
+        wake: Option<u32>,
+        log: &'static Mutex<Vec<(u32, u64)>>,
+    }
+
+    impl MessageHandler for Recorder {
+        fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+            self.log.lock().unwrap().push((self.tag, msg.data));
+            if let (1, Some(to)) = (msg.id, self.wake) {
+                ctx.send(to, Message { id: 0, data: 0 }).unwrap();
+            }
+        }
+    }
+
+    fn recorder(
+        log: &'static Mutex<Vec<(u32, u64)>>,
+        tag: u32,
+        wake: Option<u32>,
+    ) -> &'static mut dyn MessageHandler {
+        Box::leak(Box::new(Recorder { tag, wake, log }))
+    }
+
+    #[test]
+    fn test_priority_preempts_at_dispatch_boundaries() {
+        static LOG: Mutex<Vec<(u32, u64)>> = Mutex::new(Vec::new());
+        let mut scheduler = ActiveObjectScheduler::new();
+        let high = scheduler.create_object(10, recorder(&LOG, 10, None)).unwrap();
+        let low = scheduler.create_object(5, recorder(&LOG, 5, Some(high))).unwrap();
+
+        // The first low message wakes the high object, which jumps the rest of the queue
+        scheduler.send_message(low, Message { id: 1, data: 1 }).unwrap();
+        scheduler.send_message(low, Message { id: 0, data: 2 }).unwrap();
+        scheduler.send_message(low, Message { id: 0, data: 3 }).unwrap();
+        for _ in 0..5 {
+            scheduler.schedule();
+        }
+        assert_eq!(*LOG.lock().unwrap(), [(5, 1), (10, 0), (5, 2), (5, 3)]);
+
+        // Ready high priority work always goes first
+        LOG.lock().unwrap().clear();
+        scheduler.send_message(low, Message { id: 0, data: 4 }).unwrap();
+        scheduler.send_message(high, Message { id: 0, data: 5 }).unwrap();
+        scheduler.send_message(high, Message { id: 0, data: 6 }).unwrap();
+        for _ in 0..3 {
+            scheduler.schedule();
+        }
+        assert_eq!(*LOG.lock().unwrap(), [(10, 5), (10, 6), (5, 4)]);
+        assert_eq!(scheduler.stats().ready_objects, 0);
+    }
+
+    #[test]
+    fn test_equal_priorities_take_turns() {
+        static LOG: Mutex<Vec<(u32, u64)>> = Mutex::new(Vec::new());
+        let mut scheduler = ActiveObjectScheduler::new();
+        let a = scheduler.create_object(5, recorder(&LOG, 1, None)).unwrap();
+        let b = scheduler.create_object(5, recorder(&LOG, 2, None)).unwrap();
+
+        for data in 0..2 {
+            scheduler.send_message(a, Message { id: 0, data }).unwrap();
+            scheduler.send_message(b, Message { id: 0, data }).unwrap();
+        }
+        for _ in 0..4 {
+            scheduler.schedule();
+        }
+        assert_eq!(*LOG.lock().unwrap(), [(1, 0), (2, 0), (1, 1), (2, 1)]);
+    }
+
+    /// Keeps its own mailbox full, so it is always ready
+    struct Flood;
+
+    impl MessageHandler for Flood {
+        fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+            ctx.send(ctx.id(), msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_aging_bounds_starvation() {
+        static AGED: AtomicU64 = AtomicU64::new(0);
+        static STARVED: AtomicU64 = AtomicU64::new(0);
+
+        for (interval, total) in [(2, &AGED), (0, &STARVED)] {
+            let mut scheduler = ActiveObjectScheduler::new();
+            scheduler.set_aging_interval(interval);
+            let flood = scheduler.create_object(10, Box::leak(Box::new(Flood))).unwrap();
+            let background = scheduler.create_object(1, Box::leak(Box::new(Sink(total)))).unwrap();
+
+            scheduler.send_message(flood, Message { id: 0, data: 0 }).unwrap();
+            scheduler.send_message(background, Message { id: 0, data: 1 }).unwrap();
+            for _ in 0..100 {
+                scheduler.schedule();
+            }
+
+            let stats = scheduler.stats();
+            let waited = scheduler.wait_stats(background).unwrap();
+            if interval == 0 {
+                assert_eq!(waited.dispatches, 0);
+                assert_eq!(waited.current_wait, 100);
+                assert_eq!(stats.max_wait, 100);
+            } else {
+                // One level per interval until it draws level with the flood
+                assert_eq!(waited.dispatches, 1);
+                assert!(waited.max_wait <= (10 - 1 + 1) * interval as u64);
+                assert_eq!(waited.effective_priority, 1);
+                assert!(stats.promotions >= 9);
+            }
+        }
+        assert_eq!(AGED.load(Ordering::Relaxed), 1);
+        assert_eq!(STARVED.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_wait_stats() {
+        let mut scheduler = ActiveObjectScheduler::new();
+        let first = scheduler.create_object(5, noop()).unwrap();
+        let second = scheduler.create_object(5, noop()).unwrap();
+        for _ in 0..2 {
+            scheduler.send_message(first, Message { id: 0, data: 0 }).unwrap();
+        }
+        scheduler.send_message(second, Message { id: 0, data: 0 }).unwrap();
+
+        scheduler.schedule();
+        assert_eq!(scheduler.wait_stats(second).unwrap().current_wait, 1);
+        scheduler.schedule();
+        scheduler.schedule();
+
+        let stats = scheduler.stats();
+        assert_eq!(stats.dispatches, 3);
+        let (a, b) = (scheduler.wait_stats(first).unwrap(), scheduler.wait_stats(second).unwrap());
+        assert_eq!((a.dispatches, a.total_wait, a.max_wait), (2, 1, 1));
+        assert_eq!((b.dispatches, b.total_wait, b.max_wait), (1, 1, 1));
+        assert_eq!(stats.max_wait, 1);
+        assert_eq!(stats.promotions, 0);
+    }
+
+    #[test]
+    fn test_destroy_recycles_slots() {
+        let mut scheduler = ActiveObjectScheduler::new();
+        let first = scheduler.create_object(5, noop()).unwrap();
+        let second = scheduler.create_object(5, noop()).unwrap();
+        scheduler.send_message(first, Message { id: 0, data: 0 }).unwrap();
+
+        scheduler.destroy_object(first).unwrap();
+        assert!(scheduler.destroy_object(first).is_err());
+        assert_eq!(scheduler.stats().total_objects, 1);
+
+        // The slot comes back under a new id; the old one stays dead
+        let third = scheduler.create_object(5, noop()).unwrap();
+        assert_eq!(object_slot(third), object_slot(first));
+        assert_ne!(third, first);
+        assert!(scheduler.send_message(first, Message { id: 0, data: 0 }).is_err());
+        assert!(scheduler.subscribe_memory_pressure(first).is_err());
+        assert_eq!(scheduler.wait_stats(first), None);
+        scheduler.send_message(third, Message { id: 0, data: 0 }).unwrap();
+        scheduler.send_message(second, Message { id: 0, data: 0 }).unwrap();
+
+        // The table never fills up for good
+        while scheduler.create_object(1, noop()).is_ok() {}
+        assert_eq!(scheduler.stats().total_objects, MAX_OBJECTS);
+        scheduler.destroy_object(second).unwrap();
+        let last = scheduler.create_object(1, noop()).unwrap();
+        assert_eq!(object_slot(last), object_slot(second));
+        assert_eq!(scheduler.stats().ready_objects, 1);
+    }
+
+    #[test]
+    fn test_cancel_drops_outstanding_messages() {
+        static CANCELLED: AtomicU64 = AtomicU64::new(0);
+        static TOTAL: AtomicU64 = AtomicU64::new(0);
+
+        /// Sink that counts its cancellations
+        struct Cancellable(Sink);
+
+        impl MessageHandler for Cancellable {
+            fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+                self.0.handle(msg, ctx);
+            }
+
+            fn do_cancel(&mut self) {
+                CANCELLED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut scheduler = ActiveObjectScheduler::new();
+        let id = scheduler
+            .create_object(5, Box::leak(Box::new(Cancellable(Sink(&TOTAL)))))
+            .unwrap();
+        for data in 1..=3 {
+            scheduler.send_message(id, Message { id: 0, data }).unwrap();
+        }
+        scheduler.schedule();
+
+        assert_eq!(scheduler.cancel(id), Ok(2));
+        assert_eq!(CANCELLED.load(Ordering::Relaxed), 1);
+        assert_eq!(scheduler.stats().idle_objects, 1);
+        scheduler.schedule();
+        assert_eq!(scheduler.stats().dispatches, 1);
+
+        // Still alive and usable after a cancel
+        scheduler.send_message(id, Message { id: 0, data: 10 }).unwrap();
+        scheduler.schedule();
+        assert_eq!(TOTAL.load(Ordering::Relaxed), 11);
+
+        scheduler.destroy_object(id).unwrap();
+        assert_eq!(CANCELLED.load(Ordering::Relaxed), 2);
+        assert!(scheduler.cancel(id).is_err());
+    }
+
+    #[test]
+    fn test_self_destroy_runs_do_cancel() {
+        static CANCELLED: AtomicU64 = AtomicU64::new(0);
+
+        /// Destroys itself on its first message
+        struct OneShot;
+
+        impl MessageHandler for OneShot {
+            fn handle(&mut self, _msg: Message, ctx: &mut SchedulerContext) {
+                ctx.destroy(ctx.id()).unwrap();
+                assert!(ctx.destroy(ctx.id()).is_err());
+            }
+
+            fn do_cancel(&mut self) {
+                CANCELLED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut scheduler = ActiveObjectScheduler::new();
+        let id = scheduler.create_object(5, Box::leak(Box::new(OneShot))).unwrap();
+        for data in 0..2 {
+            scheduler.send_message(id, Message { id: 0, data }).unwrap();
+        }
+        scheduler.schedule();
+
+        assert_eq!(CANCELLED.load(Ordering::Relaxed), 1);
+        assert_eq!(scheduler.stats().total_objects, 0);
+        assert!(scheduler.send_message(id, Message { id: 0, data: 0 }).is_err());
+        scheduler.schedule();
+        assert_eq!(scheduler.stats().dispatches, 1);
+    }
+
+    /// Issues `data` requests per message and logs every completion it runs
+    struct Requester {
+        tag: u32,
+        issued: &'static Mutex<Vec<RequestHandle>>,
+        log: &'static Mutex<Vec<(u32, RequestHandle, i32)>>,
+    }
+
+    impl MessageHandler for Requester {
+        fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+            let kinds = [RequestKind::Timer, RequestKind::Io, RequestKind::Remote];
+            for kind in kinds.into_iter().cycle().take(msg.data as usize) {
+                self.issued.lock().unwrap().push(ctx.issue(kind).unwrap());
+            }
+        }
+
+        fn request_complete(&mut self, request: RequestHandle, status: i32, _ctx: &mut SchedulerContext) {
+            self.log.lock().unwrap().push((self.tag, request, status));
+        }
+    }
+
+    #[test]
+    fn test_request_wait_and_complete() {
+        static ISSUED: Mutex<Vec<RequestHandle>> = Mutex::new(Vec::new());
+        static LOG: Mutex<Vec<(u32, RequestHandle, i32)>> = Mutex::new(Vec::new());
+        let mut scheduler = ActiveObjectScheduler::new();
+        let id = scheduler
+            .create_object(5, Box::leak(Box::new(Requester { tag: 0, issued: &ISSUED, log: &LOG })))
+            .unwrap();
+
+        scheduler.send_message(id, Message { id: 0, data: 1 }).unwrap();
+        scheduler.schedule();
+        let timer = ISSUED.lock().unwrap()[0];
+        assert_eq!(scheduler.stats().waiting_objects, 1);
+        assert_eq!(scheduler.request_kind(timer), Some(RequestKind::Timer));
+
+        // Nothing to run until the timer fires
+        scheduler.schedule();
+        assert_eq!(scheduler.stats().dispatches, 1);
+
+        scheduler.complete_request(timer, STATUS_OK).unwrap();
+        assert!(scheduler.complete_request(timer, STATUS_OK).is_err());
+        assert_eq!(scheduler.stats().ready_objects, 1);
+        scheduler.schedule();
+        assert_eq!(*LOG.lock().unwrap(), [(0, timer, STATUS_OK)]);
+        assert_eq!(scheduler.stats().idle_objects, 1);
+    }
+
+    #[test]
+    fn test_completion_ordering() {
+        static ISSUED: Mutex<Vec<RequestHandle>> = Mutex::new(Vec::new());
+        static LOG: Mutex<Vec<(u32, RequestHandle, i32)>> = Mutex::new(Vec::new());
+        let mut scheduler = ActiveObjectScheduler::new();
+        let first = scheduler
+            .create_object(5, Box::leak(Box::new(Requester { tag: 1, issued: &ISSUED, log: &LOG })))
+            .unwrap();
+        let second = scheduler
+            .create_object(5, Box::leak(Box::new(Requester { tag: 2, issued: &ISSUED, log: &LOG })))
+            .unwrap();
+
+        scheduler.send_message(first, Message { id: 0, data: 3 }).unwrap();
+        scheduler.send_message(second, Message { id: 0, data: 1 }).unwrap();
+        scheduler.schedule();
+        scheduler.schedule();
+        let issued = ISSUED.lock().unwrap().clone();
+        assert_eq!(scheduler.stats().waiting_objects, 2);
+
+        // Each object sees its completions in signal order, not issue order;
+        // equal priorities run in the order they became ready
+        scheduler.complete_request(issued[3], -5).unwrap();
+        scheduler.complete_request(issued[2], STATUS_OK).unwrap();
+        scheduler.complete_request(issued[0], -1).unwrap();
+        scheduler.complete_request(issued[1], STATUS_OK).unwrap();
+        for _ in 0..4 {
+            scheduler.schedule();
+        }
+        assert_eq!(
+            *LOG.lock().unwrap(),
+            [(2, issued[3], -5), (1, issued[2], STATUS_OK), (1, issued[0], -1), (1, issued[1], STATUS_OK)]
+        );
+        assert_eq!(scheduler.stats().idle_objects, 2);
+    }
+
+    #[test]
+    fn test_request_cancellation() {
+        static ISSUED: Mutex<Vec<RequestHandle>> = Mutex::new(Vec::new());
+        static LOG: Mutex<Vec<(u32, RequestHandle, i32)>> = Mutex::new(Vec::new());
+        let mut scheduler = ActiveObjectScheduler::new();
+        let id = scheduler
+            .create_object(5, Box::leak(Box::new(Requester { tag: 0, issued: &ISSUED, log: &LOG })))
+            .unwrap();
+        scheduler.send_message(id, Message { id: 0, data: 3 }).unwrap();
+        scheduler.schedule();
+        let issued = ISSUED.lock().unwrap().clone();
+
+        // A cancelled request never completes; the object keeps waiting on the rest
+        scheduler.cancel_request(issued[0]).unwrap();
+        assert!(scheduler.complete_request(issued[0], STATUS_OK).is_err());
+        assert_eq!(scheduler.request_kind(issued[0]), None);
+        assert_eq!(scheduler.stats().waiting_objects, 1);
+
+        // Completed but not yet run is too late to cancel
+        scheduler.complete_request(issued[1], STATUS_OK).unwrap();
+        assert!(scheduler.cancel_request(issued[1]).is_err());
+
+        // Cancelling the object drops everything, including the undelivered completion
+        scheduler.cancel(id).unwrap();
+        assert!(scheduler.complete_request(issued[2], STATUS_OK).is_err());
+        scheduler.schedule();
+        assert!(LOG.lock().unwrap().is_empty());
+        assert_eq!(scheduler.stats().idle_objects, 1);
+
+        // Requests of a destroyed object die with it
+        scheduler.send_message(id, Message { id: 0, data: 1 }).unwrap();
+        scheduler.schedule();
+        let last = *ISSUED.lock().unwrap().last().unwrap();
+        scheduler.destroy_object(id).unwrap();
+        assert!(scheduler.complete_request(last, STATUS_OK).is_err());
+    }
+
+    #[test]
+    fn test_timer_provider() {
+        static FIRED: AtomicU64 = AtomicU64::new(0);
+        const ARM_AND_CANCEL: u32 = 1;
+
+        /// Arms a timer `data` ticks out per message
+        struct Alarm;
+
+        impl MessageHandler for Alarm {
+            fn handle(&mut self, msg: Message, ctx: &mut SchedulerContext) {
+                let timer = ctx.after(msg.data).unwrap();
+                if msg.id == ARM_AND_CANCEL {
+                    ctx.cancel_request(timer).unwrap();
+                }
+            }
+
+            fn request_complete(&mut self, request: RequestHandle, status: i32, ctx: &mut SchedulerContext) {
+                assert_eq!((request.object(), status), (ctx.id(), STATUS_OK));
+                FIRED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let mut scheduler = ActiveObjectScheduler::new();
+        let id = scheduler.create_object(5, Box::leak(Box::new(Alarm))).unwrap();
+        scheduler.send_message(id, Message { id: 0, data: 3 }).unwrap();
+        scheduler.send_message(id, Message { id: ARM_AND_CANCEL, data: 1 }).unwrap();
+        scheduler.schedule();
+        scheduler.schedule();
+        assert_eq!(scheduler.stats().waiting_objects, 1);
+
+        // The cancelled timer never fires; the other one wakes the object on its third tick
+        assert_eq!(scheduler.tick() + scheduler.tick(), 0);
+        scheduler.schedule();
+        assert_eq!(FIRED.load(Ordering::Relaxed), 0);
+        assert_eq!(scheduler.tick(), 1);
+        scheduler.schedule();
+        assert_eq!(FIRED.load(Ordering::Relaxed), 1);
+        assert_eq!(scheduler.stats().idle_objects, 1);
+
+        // Timers of a cancelled object are disarmed with it
+        scheduler.send_message(id, Message { id: 0, data: 1 }).unwrap();
+        scheduler.schedule();
+        scheduler.cancel(id).unwrap();
+        assert_eq!(scheduler.tick(), 0);
+    }
 }
@@ -1,3 +1,9 @@
 pub mod active_objects;
+pub mod ready_queue;
+pub mod request;
+pub mod timer;
 
-pub use active_objects::{ActiveObjectScheduler, Message, ObjectState, SchedulerStats};
+pub use active_objects::{
+    ActiveObjectScheduler, Message, MessageHandler, ObjectState, SchedulerContext, SchedulerStats,
+    MSG_MEMORY_PRESSURE,
+};
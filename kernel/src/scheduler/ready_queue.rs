@@ -0,0 +1,131 @@
+//! Ready queues - one FIFO per priority and a bitmap of the non-empty levels
+//! Picking the next object is a scan of four words, whatever the object count
+
+/// Distinct priorities; higher runs first
+pub const PRIORITY_LEVELS: usize = 256;
+const BITMAP_WORDS: usize = PRIORITY_LEVELS / 64;
+const NIL: u16 = u16::MAX;
+
+/// Ready objects of a scheduler with `N` slots, linked through per-slot arrays
+pub struct ReadyQueues<const N: usize> {
+    bitmap: [u64; BITMAP_WORDS],
+    head: [u16; PRIORITY_LEVELS],
+    tail: [u16; PRIORITY_LEVELS],
+    next: [u16; N],
+    prev: [u16; N],
+    priority: [u8; N],
+    queued: [bool; N],
+}
+
+impl<const N: usize> ReadyQueues<N> {
+    pub const fn new() -> Self {
+        Self {
+            bitmap: [0; BITMAP_WORDS],
+            head: [NIL; PRIORITY_LEVELS],
+            tail: [NIL; PRIORITY_LEVELS],
+            next: [NIL; N],
+            prev: [NIL; N],
+            priority: [0; N],
+            queued: [false; N],
+        }
+    }
+
+    pub fn is_queued(&self, slot: usize) -> bool {
+        self.queued[slot]
+    }
+
+    /// Queue `slot` behind the other objects of its priority; no-op if already queued
+    pub fn push(&mut self, slot: usize, priority: u8) {
+        if self.queued[slot] {
+            return;
+        }
+
+        let level = priority as usize;
+        let tail = self.tail[level];
+        self.prev[slot] = tail;
+        self.next[slot] = NIL;
+        if tail == NIL {
+            self.head[level] = slot as u16;
+            self.bitmap[level / 64] |= 1 << (level % 64);
+        } else {
+            self.next[tail as usize] = slot as u16;
+        }
+        self.tail[level] = slot as u16;
+        self.priority[slot] = priority;
+        self.queued[slot] = true;
+    }
+
+    /// Take `slot` out of its queue, wherever it is
+    pub fn remove(&mut self, slot: usize) {
+        if !self.queued[slot] {
+            return;
+        }
+
+        let level = self.priority[slot] as usize;
+        let (prev, next) = (self.prev[slot], self.next[slot]);
+        if prev == NIL {
+            self.head[level] = next;
+        } else {
+            self.next[prev as usize] = next;
+        }
+        if next == NIL {
+            self.tail[level] = prev;
+        } else {
+            self.prev[next as usize] = prev;
+        }
+        if self.head[level] == NIL {
+            self.bitmap[level / 64] &= !(1 << (level % 64));
+        }
+        self.queued[slot] = false;
+    }
+
+    /// Highest priority with a ready object
+    pub fn highest(&self) -> Option<u8> {
+        (0..BITMAP_WORDS).rev().find_map(|word| {
+            let bits = self.bitmap[word];
+            (bits != 0).then(|| (word * 64 + 63 - bits.leading_zeros() as usize) as u8)
+        })
+    }
+
+    /// Dequeue the oldest object of the highest ready priority
+    pub fn pop(&mut self) -> Option<usize> {
+        let slot = self.head[self.highest()? as usize] as usize;
+        self.remove(slot);
+        Some(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_priority_first() {
+        let mut queues = ReadyQueues::<8>::new();
+        queues.push(0, 5);
+        queues.push(1, 200);
+        queues.push(2, 5);
+        queues.push(3, 64);
+        queues.push(1, 200);
+
+        assert_eq!(queues.highest(), Some(200));
+        let order: [Option<usize>; 5] = core::array::from_fn(|_| queues.pop());
+        assert_eq!(order, [Some(1), Some(3), Some(0), Some(2), None]);
+    }
+
+    #[test]
+    fn test_remove_keeps_fifo_order() {
+        let mut queues = ReadyQueues::<8>::new();
+        for slot in 0..4 {
+            queues.push(slot, 7);
+        }
+        queues.remove(1);
+        queues.remove(3);
+        assert!(!queues.is_queued(3));
+        queues.push(3, 7);
+
+        let order: [Option<usize>; 4] = core::array::from_fn(|_| queues.pop());
+        assert_eq!(order, [Some(0), Some(2), Some(3), None]);
+        assert_eq!(queues.highest(), None);
+    }
+}
@@ -0,0 +1,167 @@
+//! Asynchronous requests - Symbian `TRequestStatus` for active objects
+//! An object issues requests, waits, and runs again once a provider signals completion
+
+/// Outstanding requests per object
+pub const MAX_REQUESTS: usize = 8;
+/// Status of a request that succeeded (Symbian `KErrNone`); failures are negative
+pub const STATUS_OK: i32 = 0;
+
+/// What a request is waiting for; the matching provider completes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Timer,
+    Io,
+    /// A call to another device over the Quantum Bus
+    Remote,
+}
+
+/// Names one request; the provider keeps it until it signals completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestHandle {
+    object: u32,
+    seq: u32,
+}
+
+impl RequestHandle {
+    pub(crate) fn new(object: u32, seq: u32) -> Self {
+        Self { object, seq }
+    }
+
+    /// Object that issued the request
+    pub fn object(&self) -> u32 {
+        self.object
+    }
+
+    pub(crate) fn seq(&self) -> u32 {
+        self.seq
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Outstanding {
+    seq: u32,
+    kind: RequestKind,
+}
+
+#[derive(Clone, Copy)]
+struct Completion {
+    seq: u32,
+    status: i32,
+}
+
+/// One object's requests: those in flight and those completed but not yet run
+pub struct RequestSet {
+    outstanding: [Option<Outstanding>; MAX_REQUESTS],
+    // Completions in the order they were signalled
+    completions: [Completion; MAX_REQUESTS],
+    completion_head: usize,
+    completion_count: usize,
+}
+
+impl RequestSet {
+    pub const fn new() -> Self {
+        Self {
+            outstanding: [None; MAX_REQUESTS],
+            completions: [Completion { seq: 0, status: 0 }; MAX_REQUESTS],
+            completion_head: 0,
+            completion_count: 0,
+        }
+    }
+
+    pub fn issue(&mut self, seq: u32, kind: RequestKind) -> Result<(), ()> {
+        // A completed request keeps its place until it has run
+        if self.outstanding_count() + self.completion_count >= MAX_REQUESTS {
+            return Err(());
+        }
+        let free = self.outstanding.iter_mut().find(|r| r.is_none()).ok_or(())?;
+        *free = Some(Outstanding { seq, kind });
+        Ok(())
+    }
+
+    /// Move an outstanding request to the completion queue
+    pub fn complete(&mut self, seq: u32, status: i32) -> Result<(), ()> {
+        self.take(seq)?;
+        let tail = (self.completion_head + self.completion_count) % MAX_REQUESTS;
+        self.completions[tail] = Completion { seq, status };
+        self.completion_count += 1;
+        Ok(())
+    }
+
+    /// Forget an outstanding request; completed ones are too late to cancel
+    pub fn cancel(&mut self, seq: u32) -> Result<(), ()> {
+        self.take(seq).map(|_| ())
+    }
+
+    /// Drop every outstanding request and undelivered completion, returning how many there were
+    pub fn cancel_all(&mut self) -> usize {
+        let dropped = self.outstanding_count() + self.completion_count;
+        self.outstanding = [None; MAX_REQUESTS];
+        self.completion_count = 0;
+        dropped
+    }
+
+    /// Oldest undelivered completion as `(seq, status)`
+    pub fn next_completion(&mut self) -> Option<(u32, i32)> {
+        if self.completion_count == 0 {
+            return None;
+        }
+        let completion = self.completions[self.completion_head];
+        self.completion_head = (self.completion_head + 1) % MAX_REQUESTS;
+        self.completion_count -= 1;
+        Some((completion.seq, completion.status))
+    }
+
+    pub fn outstanding_count(&self) -> usize {
+        self.outstanding.iter().flatten().count()
+    }
+
+    pub fn has_completions(&self) -> bool {
+        self.completion_count > 0
+    }
+
+    pub fn kind(&self, seq: u32) -> Option<RequestKind> {
+        self.outstanding.iter().flatten().find(|r| r.seq == seq).map(|r| r.kind)
+    }
+
+    fn take(&mut self, seq: u32) -> Result<Outstanding, ()> {
+        self.outstanding
+            .iter_mut()
+            .find(|r| r.is_some_and(|r| r.seq == seq))
+            .and_then(Option::take)
+            .ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completions_keep_signal_order() {
+        let mut set = RequestSet::new();
+        for seq in 1..=3 {
+            set.issue(seq, RequestKind::Io).unwrap();
+        }
+        assert_eq!(set.kind(2), Some(RequestKind::Io));
+
+        set.complete(3, -1).unwrap();
+        set.complete(1, STATUS_OK).unwrap();
+        assert!(set.complete(1, STATUS_OK).is_err());
+        assert!(set.cancel(3).is_err());
+        set.cancel(2).unwrap();
+
+        assert_eq!(set.outstanding_count(), 0);
+        assert_eq!(set.next_completion(), Some((3, -1)));
+        assert_eq!(set.next_completion(), Some((1, STATUS_OK)));
+        assert_eq!(set.next_completion(), None);
+
+        // Undelivered completions count against the limit
+        for seq in 0..MAX_REQUESTS as u32 {
+            set.issue(seq, RequestKind::Timer).unwrap();
+        }
+        set.complete(0, STATUS_OK).unwrap();
+        assert!(set.issue(99, RequestKind::Timer).is_err());
+        assert_eq!(set.cancel_all(), MAX_REQUESTS);
+        assert!(!set.has_completions());
+    }
+}
@@ -0,0 +1,106 @@
+//! Timer provider - completes `RequestKind::Timer` requests as kernel ticks go by
+//! The kernel loop advances it once per `kernel_tick`
+
+use super::request::RequestHandle;
+
+/// Timers armed at once, across all objects
+pub const MAX_TIMERS: usize = 64;
+
+#[derive(Clone, Copy)]
+struct Timer {
+    deadline: u64,
+    request: RequestHandle,
+}
+
+pub struct TimerQueue {
+    timers: [Option<Timer>; MAX_TIMERS],
+    now: u64,
+}
+
+impl TimerQueue {
+    pub const fn new() -> Self {
+        Self {
+            timers: [None; MAX_TIMERS],
+            now: 0,
+        }
+    }
+
+    /// Expire `request` once `ticks` more ticks have passed
+    pub fn arm(&mut self, request: RequestHandle, ticks: u64) -> Result<(), ()> {
+        let free = self.timers.iter_mut().find(|t| t.is_none()).ok_or(())?;
+        *free = Some(Timer {
+            deadline: self.now.saturating_add(ticks),
+            request,
+        });
+        Ok(())
+    }
+
+    /// Forget the timer behind `request`, if it is still armed
+    pub fn disarm(&mut self, request: RequestHandle) -> bool {
+        match self.timers.iter_mut().find(|t| t.is_some_and(|t| t.request == request)) {
+            Some(timer) => {
+                *timer = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget every timer `object` armed, e.g. when it is cancelled
+    pub fn disarm_object(&mut self, object: u32) {
+        for timer in &mut self.timers {
+            if timer.is_some_and(|t| t.request.object() == object) {
+                *timer = None;
+            }
+        }
+    }
+
+    pub fn advance(&mut self) {
+        self.now += 1;
+    }
+
+    /// Take the earliest timer that has run out
+    pub fn expired(&mut self) -> Option<RequestHandle> {
+        let now = self.now;
+        let timer = self
+            .timers
+            .iter_mut()
+            .filter(|t| t.is_some_and(|t| t.deadline <= now))
+            .min_by_key(|t| t.map_or(u64::MAX, |t| t.deadline))?;
+        timer.take().map(|t| t.request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timers_expire_in_deadline_order() {
+        let mut timers = TimerQueue::new();
+        let (late, early, dropped) = (RequestHandle::new(1, 0), RequestHandle::new(1, 1), RequestHandle::new(2, 2));
+        timers.arm(late, 3).unwrap();
+        timers.arm(early, 2).unwrap();
+        timers.arm(dropped, 1).unwrap();
+        assert!(timers.disarm(dropped));
+        assert!(!timers.disarm(dropped));
+
+        timers.advance();
+        assert_eq!(timers.expired(), None);
+        for _ in 0..2 {
+            timers.advance();
+        }
+        assert_eq!(timers.expired(), Some(early));
+        assert_eq!(timers.expired(), Some(late));
+        assert_eq!(timers.expired(), None);
+
+        for seq in 0..MAX_TIMERS as u32 {
+            timers.arm(RequestHandle::new(seq % 2, seq), 1).unwrap();
+        }
+        assert!(timers.arm(late, 1).is_err());
+        timers.disarm_object(0);
+        timers.disarm_object(1);
+        timers.advance();
+        assert_eq!(timers.expired(), None);
+    }
+}